@@ -0,0 +1,162 @@
+//! Solving that hands out the first solution right away and keeps shortening it until time runs out.
+//!
+//! [`improve_win`] first keeps looking for other solutions with the depth-first [`Search`],
+//! the same way [`find_multiple_wins`](crate::search::find_multiple_wins) does, until `limits.past` boards
+//! have been discovered. It then exhaustively searches around the best solution like
+//! [`optimize_solutions`](crate::search::optimize_solutions), but instead of stopping at a fixed depth
+//! it goes deeper as long as time allows, up to a search from the start proving the solution is the shortest.
+//! Every improvement is added to a [`WinnableCache`] the exhaustive searches finish early with.
+
+use std::{collections::{HashMap, HashSet}, ops::ControlFlow, time::{Duration, Instant}};
+
+use crate::{cache::WinnableCache, heuristic::Heuristic, matrix::{Matrix, Move}, packed::PackedMatrix, search::{brute_force_until, Limits, Search, SearchState}};
+
+// boards the search expands between checks of the node budget
+const STEP_BUDGET: usize = 256;
+
+/// A shorter solution than any found before.
+pub struct Improvement<'a> {
+    /// Moves winning the board.
+    pub winner: &'a [Move],
+    /// Boards discovered since starting.
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
+
+/// Finds a win and keeps looking for shorter ones for at most `time`, or until `max_nodes` boards were discovered if set.
+///
+/// `on_improvement` is called with the first solution and every shorter one after it,
+/// returning [`ControlFlow::Break`] stops the search, e.g. once a solution is good enough to play.
+/// Returns the shortest solution found.
+pub fn improve_win(
+    matrix: &Matrix,
+    allow_cheats: bool,
+    heuristic: &dyn Heuristic,
+    limits: Limits,
+    time: Duration,
+    max_nodes: Option<usize>,
+    mut on_improvement: impl FnMut(&Improvement) -> ControlFlow<()>,
+) -> Option<Vec<Move>> {
+    let start_time = Instant::now();
+    let deadline = start_time + time;
+    let limits = Limits {
+        deadline: Some(limits.deadline.map_or(deadline, |limit| limit.min(deadline))),
+        ..limits
+    };
+    let out_of_nodes = |nodes: usize| max_nodes.is_some_and(|max_nodes| nodes >= max_nodes);
+
+    let mut best_option: Option<Vec<Move>> = None;
+    let mut report = |winner: &[Move], nodes: usize| on_improvement(&Improvement {
+        winner,
+        nodes,
+        elapsed: start_time.elapsed(),
+    });
+
+    // other solutions, carrying on from every win as starting over would only find the boards discovered already
+    let mut past_matrices: HashSet<PackedMatrix> = HashSet::new();
+    past_matrices.insert(matrix.pack());
+    let mut search = Search::with_past_matrices(matrix, past_matrices, allow_cheats, heuristic, limits);
+    loop {
+        let winner = match search.step(STEP_BUDGET) {
+            SearchState::Paused if out_of_nodes(search.past_matrices().len()) => break,
+            SearchState::Paused => continue,
+            SearchState::Won(moves) => moves,
+            SearchState::Failed => break,
+        };
+        let nodes = search.past_matrices().len();
+        if best_option.as_ref().is_none_or(|best| winner.len() < best.len()) {
+            let flow = report(&winner, nodes);
+            best_option = Some(winner);
+            if flow.is_break() {
+                return best_option;
+            }
+        }
+        if !allow_cheats {
+            // there's no past limit to stop the search without cheats, leave the time to the exhaustive searches
+            break;
+        }
+    }
+    let mut nodes = search.past_matrices().len();
+
+    // exhaustive searches around the best solution, from the last moves back to the start
+    let mut best = best_option?;
+    let mut cache = WinnableCache::new();
+    cache.insert(matrix, &best);
+    let mut searched_depth = 0;
+    while searched_depth < best.len() && !limits.is_past_deadline() && !out_of_nodes(nodes) {
+        let depth = searched_depth + 1;
+        let boards = matrix.replay(&best).expect("solutions found are valid");
+
+        // only the board `depth` moves before the win is new at this depth
+        let start_len = best.len() - depth;
+        let mut discovered_matrices: HashMap<PackedMatrix, usize> = HashMap::new();
+        let nodes_left = max_nodes.map(|max_nodes| max_nodes - nodes);
+        let better_option = brute_force_until(&boards[start_len], depth, &mut discovered_matrices, allow_cheats, &cache, limits.deadline, nodes_left);
+        nodes += discovered_matrices.len();
+
+        match better_option {
+            Some(better) if start_len + better.len() < best.len() => {
+                let better = [&best[..start_len], &better[..]].concat();
+                let flow = report(&better, nodes);
+                cache.insert(matrix, &better);
+                best = better;
+                searched_depth = 0;
+                if flow.is_break() {
+                    break;
+                }
+            },
+            _ => searched_depth = depth,
+        }
+    }
+
+    Some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristic::HighestOrder;
+
+    // the moves of every improvement and the boards discovered by then, the last one being returned
+    fn improvements(matrix: &Matrix, allow_cheats: bool, time: Duration, max_nodes: Option<usize>) -> Vec<(Vec<Move>, usize)> {
+        let mut improvements = vec![];
+        let best_option = improve_win(matrix, allow_cheats, &HighestOrder, Limits::default(), time, max_nodes, |improvement| {
+            improvements.push((improvement.winner.to_vec(), improvement.nodes));
+            ControlFlow::Continue(())
+        });
+        assert_eq!(best_option.map(|best| best.len()), improvements.last().map(|(winner, _)| winner.len()));
+        improvements
+    }
+
+    #[test]
+    fn only_gets_shorter_and_keeps_winning() {
+        let mut improved = false;
+        for (deal, allow_cheats) in [(4, true), (1, true), (1, false)] {
+            let matrix = Matrix::from_deal(deal);
+            let improvements = improvements(&matrix, allow_cheats, Duration::from_secs(60), Some(10_000));
+            assert!(!improvements.is_empty());
+            improved |= improvements.len() > 1;
+            assert!(improvements.windows(2).all(|pair| pair[1].0.len() < pair[0].0.len()), "deal {} got longer", deal);
+            for (winner, _) in &improvements {
+                assert!(matrix.replay(winner).unwrap().last().unwrap().is_win());
+            }
+        }
+        assert!(improved);
+    }
+
+    #[test]
+    fn stops_at_the_node_budget() {
+        let matrix = Matrix::from_deal(4);
+        let time = Duration::from_secs(60);
+        let start_time = Instant::now();
+        let improvements = improvements(&matrix, true, time, Some(5_000));
+        assert!(start_time.elapsed() < time);
+        let first_nodes = improvements[0].1;
+        assert!(improvements.iter().all(|(_, nodes)| *nodes <= first_nodes.max(5_000)));
+
+        // the exhaustive searches on their own
+        let mut discovered_matrices = HashMap::new();
+        brute_force_until(&matrix, 30, &mut discovered_matrices, true, &WinnableCache::new(), None, Some(1_000));
+        assert_eq!(discovered_matrices.len(), 1_000);
+    }
+}
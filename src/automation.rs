@@ -0,0 +1,194 @@
+use std::{thread::sleep, time::Duration};
+use enigo::{Enigo, MouseControllable};
+use molek_syntez_solver::{rules::{MoveError, Rules}, Card, Matrix, Move};
+use screenshots::{image::{io::Reader, GenericImageView, ImageBuffer}, Screen};
+use strum::IntoEnumIterator;
+
+const BOX_WIDTH: u32 = 22;
+const BOX_HEIGHT: u32 = 18;
+const OFFSET_H: i32 = 488;
+const OFFSET_V: i32 = 298;
+const SPACE_H: i32 = 652 - OFFSET_H;
+const SPACE_V: i32 = 330 - OFFSET_V;
+
+fn card_from_image(image: ImageBuffer<screenshots::image::Rgba<u8>, Vec<u8>>) -> Option<Card> {
+    'card_loop: for card_type in Card::iter() {
+        let card_image = Reader::open(format!("assets/{}.png", card_type.to_char())).unwrap().decode().unwrap();
+        for x in 0..BOX_WIDTH {
+            for y in 0..BOX_HEIGHT {
+                if &card_image.get_pixel(x, y) != image.get_pixel(x, y) {
+                    continue 'card_loop;
+                }
+            }
+        }
+        return Some(card_type);
+    }
+    None
+}
+
+/// Reads the board from screen, `None` if any card wasn't recognized.
+pub fn matrix_from_screen() -> Option<Matrix> {
+    let mut matrix: Matrix = Default::default();
+
+    // grab the screen. This is specifically set up for my use-case
+    // aka 3 monitors at FHD, game running on middle monitor with whatever order I've set up
+    let screens = Screen::all().unwrap();
+    let screen = screens[0];
+
+    for x in 0..6 {
+        for y in 0..6 {
+            let image: ImageBuffer<screenshots::image::Rgba<u8>, Vec<u8>> = screen.capture_area(
+                OFFSET_H + (x * SPACE_H),
+                OFFSET_V + (y * SPACE_V),
+                BOX_WIDTH,
+                BOX_HEIGHT,
+            ).unwrap();
+            if let Some(card) = card_from_image(image) {
+                matrix.stacks[x as usize].cards.push(card);
+            } else {
+                return None
+            }
+        }
+    }
+
+    Some(matrix)
+}
+
+/// Plays games on screen until `target_wins` of them were won with solutions of at most `acceptable_solution_len` moves,
+/// starting with the board currently on screen if there is one.
+pub fn loop_wins(target_wins: usize, acceptable_solution_len: usize, mut find_win: impl FnMut(&Matrix) -> Option<Vec<Move>>) {
+    let mut enigo = Enigo::new();
+    let mut iter_count = 0;
+    let mut matrix_option = matrix_from_screen();
+    while iter_count < target_wins {
+        let mut matrix = if let Some(matrix) = &matrix_option {
+            matrix.copy()
+        } else {
+            // focus window
+            enigo.mouse_move_to(
+                1920 + OFFSET_H - SPACE_H,
+                OFFSET_V - SPACE_V,
+            );
+            sleep(Duration::from_millis(100));
+            enigo.mouse_down(enigo::MouseButton::Left);
+            sleep(Duration::from_millis(50));
+            enigo.mouse_up(enigo::MouseButton::Left);
+            sleep(Duration::from_millis(100));
+
+            // click new game
+            enigo.mouse_move_to(
+                1920 + (1920/2),
+                1080 - 40,
+            );
+            sleep(Duration::from_millis(100));
+            enigo.mouse_down(enigo::MouseButton::Left);
+            sleep(Duration::from_millis(50));
+            enigo.mouse_up(enigo::MouseButton::Left);
+            
+            // wait for game to be set up
+            let mut matrix_option = matrix_from_screen();
+            while matrix_option.is_none() {
+                sleep(Duration::from_millis(1500));
+                matrix_option = matrix_from_screen();
+            }
+            matrix_option.unwrap()
+        };
+        
+        // find solutions
+        // let mut winners = find_multiple_wins(matrix.copy(), allow_cheats, heuristic);
+        // let winner_option = find_multiple_wins(matrix.copy(), allow_cheats, heuristic).into_iter().next();
+        // println!("Best solution found: {} moves", winners[0].past_moves.len());
+
+        let winner_option = find_win(&matrix);
+
+        // check for optimizations and different solutions or retry
+        // if winners.is_empty() || (allow_cheats && winners[0].past_moves.len() > ACCEPTABLE_SOLUTION_LEN) {
+        //     if winners.is_empty() {
+        //         // no winners, try finding wins with a different heuristic
+        //         winners = find_multiple_wins(matrix.copy(), allow_cheats, &MoveCount);
+        //         if winners.is_empty() || (allow_cheats && winners[0].past_moves.len() > ACCEPTABLE_SOLUTION_LEN) {
+        //             if winners.is_empty() {
+        //                 println!("No solution found, reattempting...");
+        //             } else {
+        //                 println!("Secondary solution over {} moves, reattempting...", ACCEPTABLE_SOLUTION_LEN);
+        //             }
+        //             matrix_option = None;
+        //             continue;
+        //         } else {
+        //             println!("Found a secondary solution!");
+        //         }
+        //     } else {
+        //         // solution too long, try to optimize it
+        //         // made redundant by heuristic improvements, keeping for testing purposes
+        //         let winner = optimize_solutions(matrix.copy(), &winners);
+        //         winners = vec![winner];
+        //         if allow_cheats && winners[0].past_moves.len() > ACCEPTABLE_SOLUTION_LEN {
+        //             println!("Couldn't optimize primary solution, reattempting...");
+        //             matrix_option = None;
+        //             continue;
+        //         } else {
+        //             println!("Optimized primary solution!");
+        //         }
+        //     }
+        // }
+
+        // execute best solution
+        // println!("Executing solution: {} moves", winners[0].past_moves.len());
+        if let Some(winner) = winner_option {
+            if winner.len() <= acceptable_solution_len {
+                match execute_moves(&mut matrix, &winner) {
+                    Ok(()) => iter_count += 1,
+                    // the board was most likely misread, read it again
+                    Err(error) => eprintln!("Stopped executing the solution: {}", error),
+                }
+            }
+        }
+        matrix_option = None;
+    }
+}
+
+/// Clicks through `moves` on screen, updating `matrix` along the way.
+///
+/// Stops before clicking a move that's invalid on `matrix`.
+pub fn execute_moves(matrix: &mut Matrix, moves: &[Move]) -> Result<(), MoveError> {
+    let mut enigo = Enigo::new();
+
+    // focus window but don't pick a card if window already focused
+    enigo.mouse_move_to(
+        1920 + OFFSET_H - SPACE_H,
+        OFFSET_V - SPACE_V,
+    );
+    sleep(Duration::from_millis(100));
+    enigo.mouse_down(enigo::MouseButton::Left);
+    sleep(Duration::from_millis(50));
+    enigo.mouse_up(enigo::MouseButton::Left);
+    sleep(Duration::from_millis(100));
+
+    for mov in moves {
+        Rules::WITH_CHEATS.check(matrix, *mov)?;
+        let y_from = matrix.stacks[mov.from].cards.len() - mov.count;
+        enigo.mouse_move_to(
+            1920 + OFFSET_H + (mov.from as i32 * SPACE_H),
+            OFFSET_V + (y_from as i32 * SPACE_V),
+        );
+        enigo.mouse_down(enigo::MouseButton::Left);
+        sleep(Duration::from_millis(50));
+        enigo.mouse_up(enigo::MouseButton::Left);
+
+        sleep(Duration::from_millis(50));
+
+        let y_to = (matrix.stacks[mov.to].cards.len() as i32 - 1).max(0) as usize;
+        enigo.mouse_move_to(
+            1920 + OFFSET_H + (mov.to as i32 * SPACE_H),
+            OFFSET_V + (y_to as i32 * SPACE_V),
+        );
+        enigo.mouse_down(enigo::MouseButton::Left);
+        sleep(Duration::from_millis(50));
+        enigo.mouse_up(enigo::MouseButton::Left);
+
+        sleep(Duration::from_millis(100));
+
+        matrix.move_stack(*mov)?;
+    }
+    Ok(())
+}
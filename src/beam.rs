@@ -0,0 +1,78 @@
+//! Beam search, exploring the game tree one depth at a time.
+//!
+//! Every move of every board in the beam is tried, and only the `width` resulting boards scored best
+//! by the heuristic are kept for the next depth. Wins are found at the lowest depth the beam reaches them,
+//! which tends to give shorter solutions than the depth-first [`find_win`](crate::search::find_win),
+//! and memory stays bounded by the width instead of growing with the depth of the path explored.
+//! Unlike the depth-first search it can miss wins that only a discarded board leads to.
+
+use std::collections::HashSet;
+
+use crate::{database::Database, dead::is_dead, heuristic::Heuristic, matrix::{Matrix, Move, MoveValidity}, packed::PackedMatrix, rules::Rules, search::Limits};
+
+/// Beam search for a win keeping the `width` best boards per depth, lower [`Heuristic`] scores being better.
+///
+/// Every board discovered is added to `past_matrices` and never explored again, even at a later depth.
+/// When cheats are allowed, gives up after `limits.step` depths or discovering more than `limits.past` boards,
+/// and gives up at `limits.deadline` in any case.
+/// Returns the moves winning from `matrix`, finishing early on the winnable boards of `database` and skipping its dead ones.
+pub fn find_win_beam(
+    matrix: &Matrix,
+    past_matrices: &mut HashSet<PackedMatrix>,
+    allow_cheats: bool,
+    heuristic: &dyn Heuristic,
+    width: usize,
+    limits: Limits,
+    database: Option<&Database>,
+) -> Option<Vec<Move>> {
+    if matrix.is_win() {
+        return Some(vec![]);
+    }
+    let rules = Rules::new(allow_cheats);
+    past_matrices.insert(matrix.pack());
+    let start_len = matrix.past_moves.len();
+    let mut beam: Vec<Matrix> = vec![matrix.copy()];
+
+    while !beam.is_empty() {
+        if allow_cheats && (beam[0].past_moves.len() > limits.step || past_matrices.len() > limits.past) {
+            return None;
+        }
+
+        let mut scored_children: Vec<(f32, Matrix)> = vec![];
+        for matrix in &beam {
+            if limits.is_past_deadline() {
+                return None;
+            }
+            for mov in matrix.distinct_moves() {
+                if rules.validity(matrix, mov) == MoveValidity::Invalid {
+                    continue;
+                }
+                let next_matrix = matrix.copy_after_move(mov);
+                if !past_matrices.insert(next_matrix.pack()) {
+                    continue;
+                }
+                if next_matrix.is_win() {
+                    return Some(next_matrix.past_moves[start_len..].to_vec());
+                }
+                if is_dead(&next_matrix, allow_cheats) {
+                    continue;
+                }
+                if let Some(database) = database {
+                    if let Some(moves) = database.winnable().get(&next_matrix, allow_cheats) {
+                        return Some([&next_matrix.past_moves[start_len..], &moves[..]].concat());
+                    }
+                    if database.is_dead(&next_matrix, allow_cheats) {
+                        continue;
+                    }
+                }
+                scored_children.push((heuristic.score_move(matrix, mov, &next_matrix), next_matrix));
+            }
+        }
+
+        scored_children.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        scored_children.truncate(width.max(1));
+        beam = scored_children.into_iter().map(|(_, matrix)| matrix).collect();
+    }
+
+    None
+}
@@ -0,0 +1,173 @@
+//! Boards known to be winnable, remembered across searches.
+//!
+//! Every board along a solution given to [`WinnableCache::insert`] is stored with the next move of the solution
+//! and how many moves are left from it, so a later search reaching any of those boards, also coming from
+//! another deal, can finish right away by following the moves from board to board.
+//! Boards are keyed by their [`PackedMatrix`], which doesn't depend on the order of the columns,
+//! so moves are stored by position in the columns of [`Matrix::canonical_key`] and mapped back to the columns of the board looked up.
+
+use std::collections::HashMap;
+
+use crate::{matrix::{Matrix, Move, MoveValidity}, packed::PackedMatrix};
+
+#[derive(Clone, Copy)]
+struct Known {
+    // by canonical position, `None` for won boards
+    next: Option<Move>,
+    moves_left: usize,
+}
+
+/// Winnable boards with the shortest known number of moves left, kept apart for solutions without cheats.
+#[derive(Default)]
+pub struct WinnableCache {
+    with_cheats: HashMap<PackedMatrix, Known>,
+    without_cheats: HashMap<PackedMatrix, Known>,
+}
+
+impl WinnableCache {
+    pub fn new() -> WinnableCache {
+        Default::default()
+    }
+
+    /// Remembers every board along `moves` from `start`, nothing if the moves are invalid or don't win the game.
+    ///
+    /// Boards already known keep their moves unless these are shorter.
+    pub fn insert(&mut self, start: &Matrix, moves: &[Move]) {
+        let Ok(boards) = start.replay(moves) else {
+            return;
+        };
+        if !boards.last().is_some_and(Matrix::is_win) {
+            return;
+        }
+
+        let mut cheat_free = true;
+        for (index, board) in boards.iter().enumerate().rev() {
+            let (key, columns) = board.canonical_key();
+            let next = moves.get(index).map(|mov| {
+                cheat_free &= board.check_validity(*mov) != MoveValidity::ValidCheat;
+                mov.to_canonical(&columns)
+            });
+            let known = Known { next, moves_left: moves.len() - index };
+            insert_shorter(&mut self.with_cheats, key, known);
+            if cheat_free {
+                insert_shorter(&mut self.without_cheats, key, known);
+            }
+        }
+    }
+
+    /// Shortest known number of moves winning `matrix`, counting only solutions without cheats unless `allow_cheats`.
+    pub fn moves_left(&self, matrix: &Matrix, allow_cheats: bool) -> Option<usize> {
+        self.known(allow_cheats).get(&matrix.pack()).map(|known| known.moves_left)
+    }
+
+    /// Moves winning `matrix`, at most [`WinnableCache::moves_left`] of them.
+    pub fn get(&self, matrix: &Matrix, allow_cheats: bool) -> Option<Vec<Move>> {
+        let known = self.known(allow_cheats);
+        let (key, mut columns) = matrix.canonical_key();
+        let mut current = known.get(&key)?;
+        let mut board = matrix.copy();
+        let mut moves: Vec<Move> = vec![];
+        while let Some(mov) = current.next {
+            let mov = mov.from_canonical(&columns);
+            if board.move_stack(mov).is_err() {
+                return None;
+            }
+            moves.push(mov);
+            let (key, next_columns) = board.canonical_key();
+            let next = known.get(&key)?;
+            // every board reached is known with fewer moves left than the one before, unless entries were added wrongly
+            if next.moves_left >= current.moves_left {
+                return None;
+            }
+            current = next;
+            columns = next_columns;
+        }
+        Some(moves)
+    }
+
+    /// Number of boards known, with cheats or without.
+    pub fn len(&self) -> usize {
+        self.with_cheats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.with_cheats.is_empty()
+    }
+
+    /// Every board known with or without cheats, with its next move by canonical position, `None` once won, and the moves left.
+    pub(crate) fn entries(&self, allow_cheats: bool) -> impl Iterator<Item = (PackedMatrix, Option<Move>, usize)> + '_ {
+        self.known(allow_cheats).iter().map(|(key, known)| (*key, known.next, known.moves_left))
+    }
+
+    /// Adds a board as listed by [`WinnableCache::entries`], unless it's already known with fewer moves left.
+    ///
+    /// Following the next moves has to lead to boards added as well.
+    pub(crate) fn insert_entry(&mut self, key: PackedMatrix, allow_cheats: bool, next: Option<Move>, moves_left: usize) {
+        let known = if allow_cheats { &mut self.with_cheats } else { &mut self.without_cheats };
+        insert_shorter(known, key, Known { next, moves_left });
+    }
+
+    fn known(&self, allow_cheats: bool) -> &HashMap<PackedMatrix, Known> {
+        if allow_cheats {
+            &self.with_cheats
+        } else {
+            &self.without_cheats
+        }
+    }
+}
+
+fn insert_shorter(known: &mut HashMap<PackedMatrix, Known>, key: PackedMatrix, new: Known) {
+    known
+        .entry(key)
+        .and_modify(|old| if new.moves_left < old.moves_left { *old = new })
+        .or_insert(new);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+    use super::*;
+    use crate::{heuristic::HighestOrder, search::{find_win, Limits}};
+
+    #[test]
+    fn moves_follow_the_column_order_looked_up() {
+        let mut rng = SmallRng::seed_from_u64(18);
+        let mut compared = 0;
+        for deal in 1..6 {
+            let matrix = Matrix::from_deal(deal);
+            for allow_cheats in [false, true] {
+                let Some(winner) = find_win(&matrix, &mut HashSet::new(), allow_cheats, &HighestOrder, Limits::default(), None) else {
+                    continue;
+                };
+                let mut cache = WinnableCache::new();
+                cache.insert(&matrix, &winner);
+                for (index, board) in matrix.replay(&winner).unwrap().iter().enumerate().step_by(7) {
+                    let mut permuted = board.copy();
+                    permuted.past_moves.clear();
+                    permuted.stacks.shuffle(&mut rng);
+                    let moves = cache.get(&permuted, allow_cheats).unwrap();
+                    assert_eq!(moves.len(), winner.len() - index);
+                    assert_eq!(cache.moves_left(&permuted, allow_cheats), Some(moves.len()));
+                    assert!(permuted.replay(&moves).unwrap().last().unwrap().is_win());
+                    compared += 1;
+                }
+            }
+        }
+        assert!(compared > 0);
+    }
+
+    #[test]
+    fn cheats_are_only_known_with_cheats() {
+        let matrix = Matrix::from_deal(4);
+        let winner = find_win(&matrix, &mut HashSet::new(), true, &HighestOrder, Limits::default(), None).unwrap();
+        let boards = matrix.replay(&winner).unwrap();
+        assert!(winner.iter().zip(&boards).any(|(mov, board)| board.check_validity(*mov) == MoveValidity::ValidCheat));
+        let mut cache = WinnableCache::new();
+        cache.insert(&matrix, &winner);
+        assert!(cache.get(&matrix, true).is_some());
+        assert!(cache.get(&matrix, false).is_none());
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Serialize, Serializer};
+use strum_macros::EnumIter;
+
+/// One of the nine card values, ordered from `Six` (lowest) to `Tits` (highest).
+///
+/// Each value appears four times in a deal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, EnumIter)]
+pub enum Card {
+    Tits = 8,
+    King = 7,
+    Diva = 6,
+    Viva = 5,
+    Ten = 4,
+    Nine = 3,
+    Eight = 2,
+    Seven = 1,
+    Six = 0,
+}
+
+impl Card {
+    /// Parses a card from its character, `1` is accepted as an alias for `0` (ten).
+    pub fn from_char(value: char) -> Option<Self> {
+        match value {
+            't' | 'T' => Some(Card::Tits),
+            'k' | 'K' => Some(Card::King),
+            'd' | 'D' => Some(Card::Diva),
+            'v' | 'V' => Some(Card::Viva),
+            '0' | '1' => Some(Card::Ten),
+            '9' => Some(Card::Nine),
+            '8' => Some(Card::Eight),
+            '7' => Some(Card::Seven),
+            '6' => Some(Card::Six),
+            _ => None,
+        }
+    }
+
+    /// The card with `value` as its discriminant, `0` for `Six` up to `8` for `Tits`.
+    pub fn from_value(value: usize) -> Option<Self> {
+        match value {
+            8 => Some(Card::Tits),
+            7 => Some(Card::King),
+            6 => Some(Card::Diva),
+            5 => Some(Card::Viva),
+            4 => Some(Card::Ten),
+            3 => Some(Card::Nine),
+            2 => Some(Card::Eight),
+            1 => Some(Card::Seven),
+            0 => Some(Card::Six),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Card::Tits => 'T',
+            Card::King => 'K',
+            Card::Diva => 'D',
+            Card::Viva => 'V',
+            Card::Ten => '0',
+            Card::Nine => '9',
+            Card::Eight => '8',
+            Card::Seven => '7',
+            Card::Six => '6',
+        }
+    }
+}
+
+// cards are written as their character, same as in the text format
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.to_char())
+    }
+}
@@ -0,0 +1,288 @@
+//! Boards whose outcome is known, kept in a file between runs.
+//!
+//! A [`Database`] holds a [`WinnableCache`] of boards known to be winnable with their distance to the win,
+//! and the boards known to be dead, which can't be won without cheats.
+//! Searches given a database finish as soon as they discover a winnable board and never explore a dead one,
+//! and [`Database::learn`] adds what a search found out, so solving gets faster the more the database is used.
+//! Late-game boards with few stacks left to collapse are reached by many deals, which is where it helps most.
+//!
+//! Dead boards are only learned from searches that explored every board reachable without cheats
+//! and found no win, every board they discovered is dead then.
+//!
+//! The file starts with `MSDB` and a little-endian `u32` version, followed by one 32 byte record per board:
+//! the three words of its [`PackedMatrix`], a kind byte, the next move as `from`, `to` and `count` bytes
+//! with a `count` of 0 for won boards, and the moves left as a little-endian `u32`.
+
+use std::{collections::HashSet, fs, io::{self, ErrorKind}, path::Path};
+
+use crate::{cache::WinnableCache, matrix::{Matrix, Move}, packed::PackedMatrix, search::{Limits, Strategy}};
+
+const MAGIC: &[u8; 4] = b"MSDB";
+const VERSION: u32 = 1;
+const RECORD_LEN: usize = 32;
+
+const KIND_WINNABLE: u8 = 0;
+const KIND_WINNABLE_WITHOUT_CHEATS: u8 = 1;
+const KIND_DEAD: u8 = 2;
+
+#[derive(Default)]
+pub struct Database {
+    winnable: WinnableCache,
+    // no win without cheats
+    dead: HashSet<PackedMatrix>,
+}
+
+impl Database {
+    pub fn new() -> Database {
+        Default::default()
+    }
+
+    /// Reads the database at `path`, an empty one if the file doesn't exist yet.
+    pub fn open(path: &Path) -> io::Result<Database> {
+        match fs::read(path) {
+            Ok(bytes) => Database::from_bytes(&bytes),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Database::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writes the database to `path`, replacing the file only once it's fully written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let temporary_path = path.with_extension("tmp");
+        fs::write(&temporary_path, self.to_bytes())?;
+        fs::rename(&temporary_path, path)
+    }
+
+    pub fn winnable(&self) -> &WinnableCache {
+        &self.winnable
+    }
+
+    /// The winnable boards, for searches adding the solutions they find themselves.
+    pub fn winnable_mut(&mut self) -> &mut WinnableCache {
+        &mut self.winnable
+    }
+
+    /// Whether `matrix` is known not to be winnable, which is only ever known without cheats.
+    pub fn is_dead(&self, matrix: &Matrix, allow_cheats: bool) -> bool {
+        !allow_cheats && self.dead.contains(&matrix.pack())
+    }
+
+    /// Number of winnable and dead boards known.
+    pub fn len(&self) -> usize {
+        self.winnable.len() + self.dead.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.winnable.is_empty() && self.dead.is_empty()
+    }
+
+    /// Remembers every board along `moves` from `matrix`, which have to win the game.
+    pub fn insert_win(&mut self, matrix: &Matrix, moves: &[Move]) {
+        self.winnable.insert(matrix, moves);
+    }
+
+    /// Remembers the outcome of searching `matrix` with `strategy`, which discovered the boards in `past_matrices`.
+    ///
+    /// Solutions are always added, failures only mark boards dead if the search couldn't have missed a win:
+    /// a depth-first search without cheats that wasn't cut short by the deadline.
+    pub fn learn(&mut self, matrix: &Matrix, strategy: Strategy, allow_cheats: bool, limits: Limits, winner_option: Option<&[Move]>, past_matrices: &HashSet<PackedMatrix>) {
+        match winner_option {
+            Some(winner) => self.insert_win(matrix, winner),
+            None if !allow_cheats && !matches!(strategy, Strategy::Beam(_)) && !limits.is_past_deadline() => {
+                self.dead.insert(matrix.pack());
+                self.dead.extend(past_matrices);
+            },
+            None => {},
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        for (kind, allow_cheats) in [(KIND_WINNABLE, true), (KIND_WINNABLE_WITHOUT_CHEATS, false)] {
+            for (key, next, moves_left) in self.winnable.entries(allow_cheats) {
+                write_record(&mut bytes, key, kind, next, moves_left);
+            }
+        }
+        for key in &self.dead {
+            write_record(&mut bytes, *key, KIND_DEAD, None, 0);
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Database> {
+        let invalid = |message: &str| io::Error::new(ErrorKind::InvalidData, message.to_string());
+        if bytes.len() < 8 || &bytes[..4] != MAGIC {
+            return Err(invalid("not a board database"));
+        }
+        if bytes[4..8] != VERSION.to_le_bytes() {
+            return Err(invalid("unsupported board database version"));
+        }
+        let records = &bytes[8..];
+        if !records.len().is_multiple_of(RECORD_LEN) {
+            return Err(invalid("truncated board database"));
+        }
+
+        let mut database = Database::new();
+        for record in records.chunks_exact(RECORD_LEN) {
+            let word = |index: usize| u64::from_le_bytes(record[index * 8..index * 8 + 8].try_into().unwrap());
+            let key = PackedMatrix::from_words([word(0), word(1), word(2)]);
+            let [kind, from, to, count] = [record[24], record[25], record[26], record[27]].map(usize::from);
+            let moves_left = u32::from_le_bytes(record[28..32].try_into().unwrap()) as usize;
+            let next = match count {
+                0 => None,
+                1..=9 if from < 6 && to < 6 => Some(Move { from, to, count }),
+                _ => return Err(invalid("invalid move in board database")),
+            };
+            match kind as u8 {
+                KIND_WINNABLE => database.winnable.insert_entry(key, true, next, moves_left),
+                KIND_WINNABLE_WITHOUT_CHEATS => database.winnable.insert_entry(key, false, next, moves_left),
+                KIND_DEAD => {
+                    database.dead.insert(key);
+                },
+                _ => return Err(invalid("invalid record kind in board database")),
+            }
+        }
+        Ok(database)
+    }
+}
+
+fn write_record(bytes: &mut Vec<u8>, key: PackedMatrix, kind: u8, next: Option<Move>, moves_left: usize) {
+    for word in key.to_words() {
+        bytes.extend(word.to_le_bytes());
+    }
+    let Move { from, to, count } = next.unwrap_or(Move { from: 0, to: 0, count: 0 });
+    bytes.extend([kind, from as u8, to as u8, count as u8]);
+    bytes.extend((moves_left as u32).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{heuristic::HighestOrder, prover::{prove, Verdict}, search::find_win, testing::STUCK};
+
+    const PROOF_NODES: usize = 20_000;
+
+    // records sorted, the order they're written in follows the hash sets
+    fn records(bytes: &[u8]) -> Vec<&[u8]> {
+        let mut records: Vec<&[u8]> = bytes[8..].chunks(RECORD_LEN).collect();
+        records.sort();
+        records
+    }
+
+    fn search_and_learn(database: &mut Database, matrix: &Matrix, strategy: Strategy, allow_cheats: bool, limits: Limits) -> Option<Vec<Move>> {
+        let mut past_matrices = HashSet::new();
+        let winner_option = strategy.find_win(matrix, &mut past_matrices, allow_cheats, &HighestOrder, limits, Some(database));
+        database.learn(matrix, strategy, allow_cheats, limits, winner_option.as_deref(), &past_matrices);
+        winner_option
+    }
+
+    fn unwinnable() -> Matrix {
+        STUCK.parse().unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut database = Database::new();
+        for deal in 0..6 {
+            for allow_cheats in [false, true] {
+                search_and_learn(&mut database, &Matrix::from_deal(deal), Strategy::DepthFirst, allow_cheats, Limits::default());
+            }
+        }
+        search_and_learn(&mut database, &unwinnable(), Strategy::DepthFirst, false, Limits::default());
+        assert!(!database.winnable().is_empty() && !database.dead.is_empty());
+
+        let bytes = database.to_bytes();
+        assert_eq!(&bytes[..4], b"MSDB");
+        assert_eq!(bytes[4..8], 1u32.to_le_bytes());
+
+        let read = Database::from_bytes(&bytes).unwrap();
+        assert_eq!(read.len(), database.len());
+        assert_eq!(records(&read.to_bytes()), records(&bytes));
+        for deal in 0..6 {
+            let matrix = Matrix::from_deal(deal);
+            for allow_cheats in [false, true] {
+                assert_eq!(read.winnable().moves_left(&matrix, allow_cheats), database.winnable().moves_left(&matrix, allow_cheats));
+            }
+        }
+        assert!(read.is_dead(&unwinnable(), false));
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let mut database = Database::new();
+        search_and_learn(&mut database, &unwinnable(), Strategy::DepthFirst, false, Limits::default());
+        let bytes = database.to_bytes();
+        assert!(Database::from_bytes(&bytes).is_ok());
+
+        let broken = |change: &dyn Fn(&mut Vec<u8>)| {
+            let mut bytes = bytes.clone();
+            change(&mut bytes);
+            Database::from_bytes(&bytes).is_err()
+        };
+        assert!(broken(&|bytes| bytes.truncate(3)));
+        assert!(broken(&|bytes| bytes[0] = b'X'));
+        assert!(broken(&|bytes| bytes[4] = 2));
+        assert!(broken(&|bytes| {
+            bytes.pop();
+        }));
+        // record kind
+        assert!(broken(&|bytes| bytes[8 + 24] = 7));
+        // move count past a full run
+        assert!(broken(&|bytes| bytes[8 + 27] = 10));
+        // move from a stack that doesn't exist
+        assert!(broken(&|bytes| bytes[8 + 25..8 + 28].copy_from_slice(&[6, 0, 1])));
+    }
+
+    #[test]
+    fn only_complete_searches_learn_dead_boards() {
+        let matrix = unwinnable();
+        let past_deadline = Limits { deadline: Some(Instant::now()), ..Limits::default() };
+        for (strategy, allow_cheats, limits) in [
+            (Strategy::DepthFirst, true, Limits::default()),
+            (Strategy::Beam(10), false, Limits::default()),
+            (Strategy::DepthFirst, false, past_deadline),
+        ] {
+            let mut database = Database::new();
+            assert!(search_and_learn(&mut database, &matrix, strategy, allow_cheats, limits).is_none());
+            assert!(!database.is_dead(&matrix, false), "{} with cheats {} learned dead boards", strategy, allow_cheats);
+            assert!(database.is_empty());
+        }
+
+        let mut database = Database::new();
+        search_and_learn(&mut database, &matrix, Strategy::DepthFirst, false, Limits::default());
+        assert!(database.is_dead(&matrix, false));
+        // dead boards are only known without cheats
+        assert!(!database.is_dead(&matrix, true));
+    }
+
+    #[test]
+    fn winnable_boards_are_never_dead() {
+        let mut database = Database::new();
+        let mut proven = 0;
+        for deal in 0..8 {
+            let matrix = Matrix::from_deal(deal);
+            search_and_learn(&mut database, &matrix, Strategy::DepthFirst, false, Limits::default());
+            let Verdict::Solvable(winner) = prove(&matrix, false, Some(PROOF_NODES)).verdict else {
+                continue;
+            };
+            proven += 1;
+            for (step, board) in matrix.replay(&winner).unwrap().iter().enumerate() {
+                assert!(!database.is_dead(board, false), "deal {} dead after {} moves of a solution", deal, step);
+            }
+        }
+        assert!(proven > 0);
+        assert!(!database.dead.is_empty());
+
+        // winning boards found by the search agree too
+        for deal in 0..8 {
+            let matrix = Matrix::from_deal(deal);
+            if find_win(&matrix, &mut HashSet::new(), false, &HighestOrder, Limits::default(), None).is_some() {
+                assert!(!database.is_dead(&matrix, false));
+                assert!(database.winnable().moves_left(&matrix, false).is_some());
+            }
+        }
+    }
+}
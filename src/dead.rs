@@ -0,0 +1,234 @@
+//! Recognizing boards that can't be won anymore without searching them.
+//!
+//! A won board has every card in one of four collapsed stacks, with a Tits at the bottom and every other card
+//! directly on the value above it. So some cards have to move at least once before the game can be won:
+//! a card other than a Tits at the bottom of its stack, a Tits on top of another card,
+//! and any card sitting on something other than the value above it. [`is_dead`] works out which cards
+//! could ever move at all, and the board is dead if one of the cards that have to move never can.
+//!
+//! Looking at the first move of each card, when a card first moves either
+//! - every card that started above it moved before, or
+//! - it's the bottom of the moved run, so the cards still above it started in order on it and the ones above those moved before, or
+//! - it's carried along by the card below it, which started as the value above it;
+//!
+//! and unless carried along it goes on
+//! - a copy of the value above it, one that either moved before, or is still where it started with every card
+//!   that started above it moved away, in a stack that isn't collapsed and isn't the card's own stack,
+//!   and not the top card of a cheated stack, which keeps its stack cheated until it moves, or
+//! - an empty stack, one that started empty or whose cards all moved before, or
+//! - with cheats and as a single card, any other stack that isn't collapsed and isn't cheated or has its top card
+//!   moved before, unless the card is the top card of a cheated stack itself, which can't be cheated away.
+//!
+//! Starting from no cards and adding every card meeting these conditions given the ones added so far until nothing
+//! changes gives every card that could ever move and possibly more, so a card left out is provably stuck where it is.
+//! That finds, among others, cards buried under a cheated card with nowhere to go, a Tits buried in a stack
+//! that can't be cleared while no stack can ever become empty, and cards that can only go on the value above them
+//! when every copy of it lies underneath them. Cheats can take almost any card almost anywhere though,
+//! so with cheats allowed it mostly recognizes boards where cheated stacks block each other.
+//!
+//! The searches drop dead boards as they discover them, [`prove`](crate::prover::prove) doesn't, so it can check this.
+
+use crate::{card::Card, matrix::Matrix};
+
+/// Whether `matrix` provably can't be won, because a card that has to move before the win never can.
+///
+/// Never true for a board that can be won, but a board it's false for isn't necessarily winnable.
+pub fn is_dead(matrix: &Matrix, allow_cheats: bool) -> bool {
+    let stacks = &matrix.stacks;
+    // one bit per card position, for every stack
+    let mut has_to_move = [0u64; 6];
+    for (s, stack) in stacks.iter().enumerate().filter(|(_, stack)| !stack.collapsed) {
+        for (i, card) in stack.cards.iter().enumerate() {
+            let in_place = match i {
+                0 => *card == Card::Tits,
+                _ => *card as usize + 1 == stack.cards[i - 1] as usize,
+            };
+            if !in_place {
+                has_to_move[s] |= 1 << i;
+            }
+        }
+    }
+
+    let mut movable = [0u64; 6];
+    loop {
+        if (0..6).all(|s| has_to_move[s] & !movable[s] == 0) {
+            return false;
+        }
+
+        let mut can_empty = [false; 6];
+        // not cheated, or its cheated top card moved
+        let mut can_receive = [false; 6];
+        // by value, whether any copy moved, and the stacks an unmoved copy could be the top card of
+        let mut moved_value = [false; 9];
+        let mut top_value_stacks = [0u8; 9];
+        for (s, stack) in stacks.iter().enumerate().filter(|(_, stack)| !stack.collapsed) {
+            let len = stack.cards.len();
+            let mut cleared = true;
+            for i in (0..len).rev() {
+                let value = stack.cards[i] as usize;
+                let moved = movable[s] & 1 << i != 0;
+                if moved {
+                    moved_value[value] = true;
+                } else if cleared && !(stack.cheated && i + 1 == len) {
+                    top_value_stacks[value] |= 1 << s;
+                }
+                cleared &= moved;
+            }
+            can_empty[s] = cleared;
+            can_receive[s] = !stack.cheated || movable[s] & 1 << (len - 1) != 0;
+        }
+
+        let mut changed = false;
+        for (s, stack) in stacks.iter().enumerate().filter(|(_, stack)| !stack.collapsed) {
+            let cards = &stack.cards;
+            let len = cards.len();
+            let movable = &mut movable[s];
+            let other_stacks = !(1u8 << s);
+            let empty_elsewhere = (0..6).any(|t| t != s && can_empty[t]);
+            let receiver_elsewhere = (0..6).any(|t| t != s && can_receive[t]);
+
+            // from the top down, so a card freed here already counts for the ones below it
+            let mut cleared_above = true;
+            // every card above the top of the orderly run this card starts moved
+            let mut run_cleared_above = true;
+            for i in (0..len).rev() {
+                let value = cards[i] as usize;
+                if i + 1 < len {
+                    cleared_above &= *movable & 1 << (i + 1) != 0;
+                    if cards[i + 1] as usize + 1 != value {
+                        run_cleared_above = cleared_above;
+                    }
+                }
+                if *movable & 1 << i != 0 {
+                    continue;
+                }
+
+                let successor_on_top = value < Card::Tits as usize
+                    && (moved_value[value + 1] || top_value_stacks[value + 1] & other_stacks != 0);
+                let placeable = run_cleared_above && (successor_on_top || empty_elsewhere);
+
+                let cheatable = allow_cheats
+                    && cleared_above
+                    && receiver_elsewhere
+                    && !(stack.cheated && i + 1 == len);
+
+                if placeable || cheatable {
+                    *movable |= 1 << i;
+                    changed = true;
+                }
+            }
+
+            // cards carried along by the card below them
+            for i in 1..len {
+                if *movable & 1 << i == 0 && *movable & 1 << (i - 1) != 0 && cards[i - 1] as usize == cards[i] as usize + 1 {
+                    *movable |= 1 << i;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::{prover::{prove, Verdict}, search::{find_win, Limits}, heuristic::HighestOrder, testing::{random_walk, STUCK}};
+
+    const PROOF_NODES: usize = 20_000;
+
+    fn board(text: &str) -> Matrix {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn deals_are_not_dead() {
+        for deal in 0..200 {
+            let matrix = Matrix::from_deal(deal);
+            for allow_cheats in [false, true] {
+                if is_dead(&matrix, allow_cheats) {
+                    assert!(!matches!(prove(&matrix, allow_cheats, None).verdict, Verdict::Solvable(_)), "deal {} wrongly dead", deal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn won_board_is_not_dead() {
+        let won = board("
+            T T T T . .
+            K K K K . .
+            D D D D . .
+            V V V V . .
+            0 0 0 0 . .
+            9 9 9 9 . .
+            8 8 8 8 . .
+            7 7 7 7 . .
+            6 6 6 6 . .
+            C C C C . .
+        ");
+        assert!(!is_dead(&won, false));
+        assert!(!is_dead(&won, true));
+    }
+
+    #[test]
+    fn buried_tits_without_empty_stacks_is_dead() {
+        // every Tits needs an empty stack, but no stack can ever be emptied
+        let matrix = board("
+            6 7 8 9 0 V
+            T T T T D K
+            6 7 8 9 0 V
+            D K 6 7 8 9
+            0 V D K 6 7
+            8 9 0 V D K
+        ");
+        assert!(is_dead(&matrix, false));
+        assert!(matches!(prove(&matrix, false, None).verdict, Verdict::Unsolvable));
+    }
+
+    #[test]
+    fn cards_under_stuck_cheats_are_dead() {
+        let matrix = board(STUCK);
+        assert!(is_dead(&matrix, true));
+        assert!(is_dead(&matrix, false));
+        assert!(matches!(prove(&matrix, true, None).verdict, Verdict::Unsolvable));
+    }
+
+    #[test]
+    fn never_dead_along_a_solution() {
+        for deal in 0..10 {
+            let matrix = Matrix::from_deal(deal);
+            for allow_cheats in [false, true] {
+                let Some(winner) = find_win(&matrix, &mut Default::default(), allow_cheats, &HighestOrder, Limits::default(), None) else {
+                    continue;
+                };
+                for (step, next) in matrix.replay(&winner).unwrap().iter().enumerate() {
+                    assert!(!is_dead(next, allow_cheats), "deal {} dead after {} moves of a solution", deal, step);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn never_dead_when_winnable() {
+        let mut rng = SmallRng::seed_from_u64(20);
+        let mut dead = 0;
+        for deal in 0..30 {
+            for allow_cheats in [false, true] {
+                for matrix in random_walk(deal, allow_cheats, 40, &mut rng).iter().step_by(4) {
+                    if !is_dead(matrix, allow_cheats) {
+                        continue;
+                    }
+                    dead += 1;
+                    let verdict = prove(matrix, allow_cheats, Some(PROOF_NODES)).verdict;
+                    assert!(!matches!(verdict, Verdict::Solvable(_)), "winnable board detected as dead:\n{}", matrix);
+                }
+            }
+        }
+        assert!(dead > 0);
+    }
+}
@@ -0,0 +1,103 @@
+//! Numbered deals that stay the same across versions, so they can be referenced by number.
+//!
+//! Deal `n` is generated by shuffling the deck `67890VDKT` repeated four times
+//! with a Fisher-Yates shuffle (from the last card down to the second one, swapping card `i`
+//! with a card at index `0..=i`), drawing indices from a SplitMix64 generator seeded with `n`
+//! and rejecting draws that would bias the result. Card `i` of the shuffled deck then goes
+//! on top of stack `i % 6`.
+//!
+//! [`Matrix::random`] depends on the `rand` implementation and may change when it's updated,
+//! this doesn't.
+
+use crate::{card::Card, matrix::Matrix};
+
+const DECK: &str = "67890VDKT";
+
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    // uniform in 0..bound
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+impl Matrix {
+    /// Deal number `deal`, see the [module docs](crate::deal) for how it's generated.
+    pub fn from_deal(deal: u64) -> Matrix {
+        let mut rng = SplitMix64 { state: deal };
+        let mut cards: Vec<Card> = DECK
+            .repeat(4)
+            .chars()
+            .map(|character| Card::from_char(character).unwrap())
+            .collect();
+
+        for i in (1..cards.len()).rev() {
+            let j = rng.below(i as u64 + 1) as usize;
+            cards.swap(i, j);
+        }
+
+        let mut matrix: Matrix = Default::default();
+        for (i, card) in cards.into_iter().enumerate() {
+            matrix.stacks[i % 6].cards.push(card);
+        }
+        matrix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // changing the generator or the shuffle would renumber every deal
+    #[test]
+    fn deals_never_change() {
+        let expected = [
+            (0, "
+                8 K 8 T 7 6
+                9 8 0 6 T T
+                D 0 D 7 V 9
+                0 V 0 9 V 9
+                7 7 8 K T V
+                D 6 K 6 D K
+            "),
+            (1, "
+                9 0 T 9 V 0
+                K 0 T K T 9
+                6 D 6 D D 8
+                9 V 8 7 0 6
+                8 K 7 T 7 D
+                7 K 8 6 V V
+            "),
+            (42, "
+                6 0 V 0 8 D
+                K 7 V K 6 K
+                9 9 T T 0 D
+                D 6 V 7 T V
+                8 9 8 0 7 K
+                8 6 9 D T 7
+            "),
+        ];
+        for (deal, text) in expected {
+            let rows: Vec<&str> = text.lines().map(str::trim).filter(|row| !row.is_empty()).collect();
+            let printed = Matrix::from_deal(deal).to_string();
+            assert_eq!(printed.lines().map(str::trim_end).collect::<Vec<_>>(), rows, "deal {}", deal);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Telling boards apart by a hash that's kept up to date as moves are made and taken back.
+//!
+//! A [`Fingerprint`] hashes every stack on its own and adds those up, so like a [`PackedMatrix`](crate::packed::PackedMatrix)
+//! it doesn't depend on the column order, and after a move only the two stacks it touched are hashed again.
+//! Different boards can share a fingerprint though, so only different fingerprints say anything for sure,
+//! telling whether a board was reached before still takes its packed board.
+
+use crate::{matrix::{Matrix, Move}, stack::Stack};
+
+/// Hash of a board, the same for boards that only differ in their column order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fingerprint {
+    stacks: [u64; 6],
+    value: u64,
+}
+
+impl Fingerprint {
+    pub fn new(matrix: &Matrix) -> Fingerprint {
+        let stacks = matrix.stacks.each_ref().map(hash_stack);
+        Fingerprint {
+            stacks,
+            value: stacks.iter().fold(0, |sum, hash| sum.wrapping_add(*hash)),
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Catches up with `mov` having been made on `matrix`, or taken back from it.
+    pub fn update(&mut self, matrix: &Matrix, mov: Move) {
+        for column in [mov.from, mov.to] {
+            let hash = hash_stack(&matrix.stacks[column]);
+            self.value = self.value.wrapping_sub(self.stacks[column]).wrapping_add(hash);
+            self.stacks[column] = hash;
+        }
+    }
+}
+
+// the finalizer of splitmix64
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+fn hash_stack(stack: &Stack) -> u64 {
+    let flags = stack.cheated as u64 | (stack.collapsed as u64) << 1;
+    // cards counted from 1 so a Six changes the hash too
+    let cards = stack.cards.iter().fold(mix(flags + 1), |hash, card| mix(hash ^ (*card as u64 + 1)));
+    mix(cards.wrapping_add(0x9e3779b97f4a7c15))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::testing::random_walk;
+
+    #[test]
+    fn updates_match_hashing_again() {
+        let mut rng = SmallRng::seed_from_u64(25);
+        for deal in 0..20 {
+            let walked = random_walk(deal, true, 30, &mut rng);
+            let mut matrix = Matrix::from_deal(deal);
+            let mut fingerprint = Fingerprint::new(&matrix);
+            let mut undos = vec![];
+            for &mov in &walked.last().unwrap().past_moves {
+                undos.push(matrix.make(mov).unwrap());
+                fingerprint.update(&matrix, mov);
+                assert_eq!(fingerprint, Fingerprint::new(&matrix));
+            }
+            while let Some(undo) = undos.pop() {
+                matrix.unmake(undo);
+                fingerprint.update(&matrix, undo.mov);
+                assert_eq!(fingerprint, Fingerprint::new(&matrix));
+            }
+        }
+    }
+
+    #[test]
+    fn column_order_does_not_matter() {
+        let matrix = Matrix::from_deal(3);
+        let mut reversed = matrix.copy();
+        reversed.stacks.reverse();
+        assert_eq!(Fingerprint::new(&matrix).value(), Fingerprint::new(&reversed).value());
+        assert_ne!(Fingerprint::new(&matrix).value(), Fingerprint::new(&Matrix::from_deal(4)).value());
+    }
+}
@@ -0,0 +1,211 @@
+//! Move ordering for the search.
+//!
+//! A [`Heuristic`] scores boards and the search explores the moves leading to the lowest scores first.
+//! Besides the built-in ones, any type implementing the trait can be passed to the search,
+//! and [`Weighted`] sums several of them so they can be combined at runtime, e.g. from the command line:
+//!
+//! ```text
+//! highest-order
+//! highest-order:2,move-count:0.5
+//! collapsed-stacks:-10,orderly-runs:-1,buried-high-cards:0.5
+//! ```
+//!
+//! Scores of the board features like [`CollapsedStacks`] grow with how often the feature appears,
+//! give them a negative weight to prefer boards with more of it.
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use crate::{card::Card, matrix::{Matrix, Move}};
+
+/// Ordering used by the search to decide which move to explore first, lower scores are preferred.
+pub trait Heuristic: Send + Sync {
+    fn score(&self, matrix: &Matrix) -> f32;
+
+    /// Score of making `mov` on `matrix`, resulting in `next_matrix`.
+    ///
+    /// Scores the resulting board by default, override it to also take the move itself into account.
+    fn score_move(&self, matrix: &Matrix, mov: Move, next_matrix: &Matrix) -> f32 {
+        let _ = (matrix, mov);
+        self.score(next_matrix)
+    }
+}
+
+/// Prefer boards with fewer valid moves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MoveCount;
+
+impl Heuristic for MoveCount {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        matrix.valid_moves().len() as f32
+    }
+}
+
+/// Prefer boards with more collapsed stacks and longer ordered runs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HighestOrder;
+
+impl Heuristic for HighestOrder {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        let mut ret = 40;
+        for stack in &matrix.stacks {
+            if stack.collapsed {
+                ret -= 10
+            } else {
+                ret -= stack.highest_orderly_count()
+            }
+        }
+        ret as f32
+    }
+}
+
+/// Keep the move generation order.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Unordered;
+
+impl Heuristic for Unordered {
+    fn score(&self, _matrix: &Matrix) -> f32 {
+        0.0
+    }
+}
+
+/// Number of collapsed stacks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CollapsedStacks;
+
+impl Heuristic for CollapsedStacks {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        matrix.stacks.iter().filter(|stack| stack.collapsed).count() as f32
+    }
+}
+
+/// Total length of the ordered runs on top of the stacks that haven't collapsed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrderlyRuns;
+
+impl Heuristic for OrderlyRuns {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        matrix.stacks
+            .iter()
+            .filter(|stack| !stack.collapsed)
+            .map(|stack| stack.highest_orderly_count())
+            .sum::<usize>() as f32
+    }
+}
+
+/// Number of empty stacks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmptyStacks;
+
+impl Heuristic for EmptyStacks {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        matrix.stacks.iter().filter(|stack| stack.cards.is_empty()).count() as f32
+    }
+}
+
+/// Number of cheated stacks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CheatedStacks;
+
+impl Heuristic for CheatedStacks {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        matrix.stacks.iter().filter(|stack| stack.cheated).count() as f32
+    }
+}
+
+/// Number of divas, kings and tits buried below the top run of their stack.
+///
+/// Collapsing a stack needs them at the bottom, so every one of them that is covered has to be dug out first.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuriedHighCards;
+
+impl Heuristic for BuriedHighCards {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        matrix.stacks
+            .iter()
+            .filter(|stack| !stack.collapsed)
+            .map(|stack| {
+                let buried = stack.cards.len() - stack.highest_orderly_count();
+                stack.cards[..buried].iter().filter(|card| **card as usize >= Card::Diva as usize).count()
+            })
+            .sum::<usize>() as f32
+    }
+}
+
+/// Looks up a built-in heuristic by the name used in [`Weighted`] specs.
+pub fn by_name(name: &str) -> Option<Arc<dyn Heuristic>> {
+    match name {
+        "move-count" => Some(Arc::new(MoveCount)),
+        "highest-order" => Some(Arc::new(HighestOrder)),
+        "unordered" => Some(Arc::new(Unordered)),
+        "collapsed-stacks" => Some(Arc::new(CollapsedStacks)),
+        "orderly-runs" => Some(Arc::new(OrderlyRuns)),
+        "empty-stacks" => Some(Arc::new(EmptyStacks)),
+        "cheated-stacks" => Some(Arc::new(CheatedStacks)),
+        "buried-high-cards" => Some(Arc::new(BuriedHighCards)),
+        _ => None,
+    }
+}
+
+/// Sum of several heuristics, each multiplied by its weight.
+#[derive(Clone, Default)]
+pub struct Weighted {
+    terms: Vec<(String, f32, Arc<dyn Heuristic>)>,
+}
+
+impl Weighted {
+    pub fn new() -> Weighted {
+        Default::default()
+    }
+
+    /// Adds `heuristic` with `weight`, `name` is only used to print the combination.
+    pub fn with(mut self, name: &str, weight: f32, heuristic: Arc<dyn Heuristic>) -> Weighted {
+        self.terms.push((name.to_string(), weight, heuristic));
+        self
+    }
+
+    /// Names and weights of the terms, in the order they were added.
+    pub fn weights(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.terms.iter().map(|(name, weight, _)| (name.as_str(), *weight))
+    }
+}
+
+impl Heuristic for Weighted {
+    fn score(&self, matrix: &Matrix) -> f32 {
+        self.terms.iter().map(|(_, weight, heuristic)| weight * heuristic.score(matrix)).sum()
+    }
+
+    fn score_move(&self, matrix: &Matrix, mov: Move, next_matrix: &Matrix) -> f32 {
+        self.terms.iter().map(|(_, weight, heuristic)| weight * heuristic.score_move(matrix, mov, next_matrix)).sum()
+    }
+}
+
+/// Parses comma separated `name` or `name:weight` terms, the weight defaults to 1.
+impl FromStr for Weighted {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weighted = Weighted::new();
+        for term in s.split(',').map(str::trim) {
+            let (name, weight) = match term.split_once(':') {
+                Some((name, weight)) => {
+                    let weight: f32 = weight.trim().parse().map_err(|_| format!("invalid weight in \"{}\"", term))?;
+                    (name.trim(), weight)
+                },
+                None => (term, 1.0),
+            };
+            let heuristic = by_name(name).ok_or_else(|| format!("unknown heuristic \"{}\"", name))?;
+            weighted = weighted.with(name, weight, heuristic);
+        }
+        Ok(weighted)
+    }
+}
+
+impl fmt::Display for Weighted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms: Vec<String> = self
+            .weights()
+            .map(|(name, weight)| if weight == 1.0 { name.to_string() } else { format!("{}:{}", name, weight) })
+            .collect();
+        f.write_str(&terms.join(","))
+    }
+}
@@ -0,0 +1,191 @@
+//! Taking moves back.
+//!
+//! [`Matrix::make`] makes a move and returns an [`Undo`], which [`Matrix::unmake`] takes it back with,
+//! so a search can walk a single board up and down its tree instead of copying a board for every move.
+//! A valid move never goes onto a cheated or collapsed stack, so taking it back only needs to know
+//! whether the stack the cards came from was cheated before, a cheat or collapse it caused is simply cleared.
+//!
+//! [`History`] keeps every move made on a game so it can step back and forth through it,
+//! like undo and redo in an editor.
+
+use crate::{matrix::{Matrix, Move}, rules::MoveError};
+
+/// A move made with [`Matrix::make`] and what's needed to take it back.
+#[derive(Clone, Copy, Debug)]
+pub struct Undo {
+    pub mov: Move,
+    source_cheated: bool,
+}
+
+impl Matrix {
+    /// Makes `mov` like [`Matrix::try_apply`], returning what [`Matrix::unmake`] needs to take it back.
+    pub fn make(&mut self, mov: Move) -> Result<Undo, MoveError> {
+        let source_cheated = self.stacks[mov.from].cheated;
+        self.try_apply(mov)?;
+        Ok(Undo { mov, source_cheated })
+    }
+
+    /// Takes back the last move made, `undo` has to be what [`Matrix::make`] returned for it.
+    pub fn unmake(&mut self, undo: Undo) {
+        let Move { from, to, count } = undo.mov;
+        let target = &mut self.stacks[to];
+        target.collapsed = false;
+        target.cheated = false;
+        let cards = target.cards.split_off(target.cards.len() - count);
+        let source = &mut self.stacks[from];
+        source.cards.extend(cards);
+        source.cheated = undo.source_cheated;
+        self.past_moves.pop();
+    }
+}
+
+/// A game from its start, with every move made and the ones undone since, which can be redone.
+pub struct History {
+    matrix: Matrix,
+    made: Vec<Undo>,
+    // the last one undone last
+    undone: Vec<Move>,
+}
+
+impl History {
+    /// A game starting at `start`, without any moves.
+    pub fn new(start: &Matrix) -> History {
+        let mut matrix = start.copy();
+        matrix.past_moves.clear();
+        History { matrix, made: vec![], undone: vec![] }
+    }
+
+    /// A game starting at `start` going through `moves`, at its start.
+    ///
+    /// Fails on the first invalid move, with its index in `moves`.
+    pub fn with_moves(start: &Matrix, moves: &[Move]) -> Result<History, (usize, MoveError)> {
+        let mut history = History::new(start);
+        for (index, mov) in moves.iter().enumerate() {
+            history.apply(*mov).map_err(|error| (index, error))?;
+        }
+        history.jump_to(0);
+        Ok(history)
+    }
+
+    /// The board after the moves made so far, its `past_moves` being those moves.
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    /// Number of moves made so far.
+    pub fn step(&self) -> usize {
+        self.made.len()
+    }
+
+    /// Number of moves made and undone.
+    pub fn len(&self) -> usize {
+        self.made.len() + self.undone.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every move of the game, the ones undone included.
+    pub fn moves(&self) -> Vec<Move> {
+        self.made.iter().map(|undo| undo.mov).chain(self.undone.iter().rev().copied()).collect()
+    }
+
+    /// Makes `mov`, the moves undone before can't be redone anymore.
+    pub fn apply(&mut self, mov: Move) -> Result<(), MoveError> {
+        let undo = self.matrix.make(mov)?;
+        self.made.push(undo);
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Takes back the last move made, if any.
+    pub fn undo(&mut self) -> Option<Move> {
+        let undo = self.made.pop()?;
+        self.matrix.unmake(undo);
+        self.undone.push(undo.mov);
+        Some(undo.mov)
+    }
+
+    /// Makes the last move undone again, if any.
+    pub fn redo(&mut self) -> Option<Move> {
+        let mov = self.undone.pop()?;
+        // it was made on this same board before
+        let undo = self.matrix.make(mov).expect("undone moves are valid");
+        self.made.push(undo);
+        Some(mov)
+    }
+
+    /// Undoes or redoes moves until `step` moves are made, as many as there are if `step` is past the end.
+    pub fn jump_to(&mut self, step: usize) {
+        while self.step() > step && self.undo().is_some() {}
+        while self.step() < step && self.redo().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card::Card, matrix::MoveValidity};
+
+    #[test]
+    fn unmake_restores_cheats_and_collapses() {
+        let mut matrix = Matrix::default();
+        matrix.stacks[0].cards = vec![Card::Tits, Card::King, Card::Diva, Card::Viva, Card::Ten, Card::Nine, Card::Eight, Card::Seven];
+        matrix.stacks[1].cards = vec![Card::King, Card::Six];
+        matrix.stacks[1].cheated = true;
+        matrix.stacks[2].cards = vec![Card::Eight];
+        let before = matrix.copy();
+
+        let collapse = matrix.make(Move { from: 1, to: 0, count: 1 }).unwrap();
+        assert!(matrix.stacks[0].collapsed && !matrix.stacks[1].cheated);
+        let cheat = Move { from: 2, to: 1, count: 1 };
+        assert!(matrix.check_validity(cheat) == MoveValidity::ValidCheat);
+        let cheat = matrix.make(cheat).unwrap();
+        assert!(matrix.stacks[1].cheated);
+
+        matrix.unmake(cheat);
+        matrix.unmake(collapse);
+        for (stack, expected) in matrix.stacks.iter().zip(&before.stacks) {
+            assert_eq!(stack.cards, expected.cards);
+            assert_eq!((stack.cheated, stack.collapsed), (expected.cheated, expected.collapsed));
+        }
+        assert!(matrix.past_moves.is_empty());
+    }
+
+    #[test]
+    fn undo_redo_and_jump() {
+        let start = Matrix::from_deal(0);
+        let mut boards = vec![start.copy()];
+        let mut line = vec![];
+        for _ in 0..6 {
+            let Some(mov) = boards.last().unwrap().valid_moves().into_iter().next() else {
+                break;
+            };
+            boards.push(boards.last().unwrap().copy_after_move(mov));
+            line.push(mov);
+        }
+        assert!(line.len() > 2);
+
+        let mut history = History::with_moves(&start, &line).unwrap();
+        assert_eq!((history.step(), history.len()), (0, line.len()));
+        history.jump_to(line.len());
+        assert!(*history.matrix() == boards[line.len()]);
+        assert!(history.redo().is_none());
+
+        history.jump_to(2);
+        assert!(*history.matrix() == boards[2]);
+        assert_eq!(history.matrix().past_moves.len(), 2);
+        history.undo();
+        assert!(*history.matrix() == boards[1]);
+        history.redo();
+        assert!(*history.matrix() == boards[2]);
+        assert_eq!(history.moves().len(), line.len());
+
+        // a new move drops the ones undone
+        history.undo();
+        history.apply(line[1]).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history.redo().is_none());
+    }
+}
@@ -0,0 +1,41 @@
+//! Solver core for the MOLEK-SYNTEZ Solitaire minigame.
+//!
+//! A board is a [`Matrix`] of six [`Stack`]s holding four sets of nine [`Card`]s.
+//! Cards are moved with [`Move`]s, and a stack collapses once it holds all nine values in order.
+//! The game is won when four stacks have collapsed, see [`search`] for finding such wins.
+
+pub mod anytime;
+pub mod beam;
+pub mod cache;
+pub mod card;
+pub mod database;
+pub mod dead;
+pub mod deal;
+pub mod fingerprint;
+pub mod heuristic;
+pub mod history;
+pub mod matrix;
+pub mod optimal;
+pub mod packed;
+pub mod parallel;
+pub mod portfolio;
+pub mod prover;
+pub mod rules;
+pub mod search;
+pub mod simulation;
+pub mod solution;
+pub mod stack;
+pub mod symmetry;
+#[cfg(test)]
+mod testing;
+pub mod text;
+pub mod tuning;
+
+pub use card::Card;
+pub use heuristic::Heuristic;
+pub use matrix::{Matrix, Move, MoveValidity};
+pub use packed::PackedMatrix;
+pub use search::{Limits, Strategy};
+pub use solution::Solution;
+pub use stack::Stack;
+pub use text::ParseMatrixError;
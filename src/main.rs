@@ -1,714 +1,659 @@
-use std::{collections::HashSet, hash::Hash, thread::sleep, time::{Duration, Instant}};
-use enigo::{Enigo, MouseControllable};
-use rand::{rngs::SmallRng, Rng, SeedableRng};
-use screenshots::{image::{io::Reader, GenericImageView, ImageBuffer}, Screen};
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
-
-const BOX_WIDTH: u32 = 22;
-const BOX_HEIGHT: u32 = 18;
-const OFFSET_H: i32 = 488;
-const OFFSET_V: i32 = 298;
-const SPACE_H: i32 = 652 - OFFSET_H;
-const SPACE_V: i32 = 330 - OFFSET_V;
+use std::{collections::HashSet, fs, io::{self, Read}, ops::ControlFlow, path::PathBuf, process, time::{Duration, Instant}};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use molek_syntez_solver::{anytime::improve_win, database::Database, portfolio::{default_entries, race, EntryResult}, search::{find_multiple_wins, optimize_solutions, Search, Strategy, ACCEPTABLE_SOLUTION_LEN, PAST_LIMIT, STEP_LIMIT}, optimal::find_optimal, prover::{prove, Verdict}, simulation::{run_deals, Summary}, solution::{format_moves, parse_moves}, history::History, text::parse_matrices, tuning::Tuner, heuristic::Weighted, Heuristic, Limits, Matrix, Move, PackedMatrix, Solution};
+use rand::{rngs::SmallRng, SeedableRng};
+use serde::Serialize;
+
+#[cfg(feature = "automation")]
+mod automation;
+
+const SEED: u64 = 1337;
+// deals played between saves of the database
+const SAVE_INTERVAL: usize = 10;
+
+#[derive(Parser)]
+#[command(version, about = "Automated solution finder and executioner for MOLEK-SYNTEZ Solitaire")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-// strike a balance between fast, non-breaking, not missing a solve too often
-const STEP_LIMIT: usize = 2000;
-const PAST_LIMIT: usize = 20000;
-const ACCEPTABLE_SOLUTION_LEN: usize = 100;
+#[derive(Subcommand)]
+enum Command {
+    /// Play games on screen until the target number of wins is reached.
+    /// Builds without the `automation` feature can only --dry-run
+    Play {
+        #[arg(long, default_value_t = 7)]
+        target_wins: usize,
+        /// Solve random deals instead of playing on screen
+        #[arg(long)]
+        dry_run: bool,
+        /// Seed for random deals, only used with --dry-run
+        #[arg(long, default_value_t = SEED)]
+        seed: u64,
+        /// Race several strategies on every deal for at most this many seconds, see `solve --portfolio`
+        #[arg(long, value_name = "SECONDS", conflicts_with_all = ["beam_width", "search_threads"])]
+        portfolio: Option<f32>,
+        #[command(flatten)]
+        anytime: AnytimeArgs,
+        #[command(flatten)]
+        database: DatabaseArgs,
+        #[command(flatten)]
+        solver: SolverArgs,
+    },
+    /// Solve boards from files or stdin and print the moves without executing them
+    Solve(SolveArgs),
+    /// Decide whether boards from files or stdin can be won at all, without and with cheats
+    Prove {
+        /// Files holding boards in the text format, separated by empty lines. Reads stdin if none or `-` is given
+        files: Vec<PathBuf>,
+        /// Give up on a board after exploring this many boards
+        #[arg(long)]
+        max_nodes: Option<usize>,
+        /// With `false`, only decide without cheats
+        #[arg(long, default_value_t = true, action = ArgAction::Set)]
+        allow_cheats: bool,
+    },
+    /// Find the shortest solutions of boards from files or stdin and compare them to the regular search
+    Optimal {
+        /// Files holding boards in the text format, separated by empty lines. Reads stdin if none or `-` is given
+        files: Vec<PathBuf>,
+        /// Give up on a board after expanding this many boards
+        #[arg(long)]
+        max_nodes: Option<usize>,
+        #[command(flatten)]
+        solver: SolverArgs,
+    },
+    /// Solve numbered deals without and with cheats and report statistics
+    Simulate {
+        #[arg(long, default_value_t = 100)]
+        deals: u64,
+        #[arg(long, default_value_t = 0)]
+        first_deal: u64,
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
+        #[command(flatten)]
+        database: DatabaseArgs,
+        #[command(flatten)]
+        solver: SolverArgs,
+    },
+    /// Print the board on screen
+    #[cfg(feature = "automation")]
+    Capture,
+    /// Step back and forth through moves on a board, reading `n` for next, `p` for previous, a step number or `q` from stdin
+    Replay {
+        /// File holding the board in the text format
+        file: PathBuf,
+        /// Moves in the compact format, e.g. `3->1x2 0->4x1`
+        moves: String,
+    },
+    /// Print numbered deals, these stay the same across versions
+    Deal {
+        number: u64,
+        /// Print this many consecutive deals, separated by empty lines
+        #[arg(long, default_value_t = 1)]
+        count: u64,
+    },
+    /// Time solving a number of random deals
+    Benchmark {
+        #[arg(long, default_value_t = 100)]
+        deals: usize,
+        #[arg(long, default_value_t = SEED)]
+        seed: u64,
+        #[command(flatten)]
+        solver: SolverArgs,
+    },
+    /// Search for heuristic weights that solve a set of random deals with fewer moves and nodes
+    Tune {
+        /// Number of random deals to evaluate every set of weights on
+        #[arg(long, default_value_t = 50)]
+        deals: usize,
+        #[arg(long, default_value_t = SEED)]
+        seed: u64,
+        /// Number of weight changes to try
+        #[arg(long, default_value_t = 30)]
+        iterations: usize,
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
+        #[command(flatten)]
+        solver: SolverArgs,
+    },
+}
 
-#[derive(Clone, Copy, PartialEq, Debug, EnumIter)]
-enum Card {
-    Tits = 8,
-    King = 7,
-    Diva = 6,
-    Viva = 5,
-    Ten = 4,
-    Nine = 3,
-    Eight = 2,
-    Seven = 1,
-    Six = 0,
+#[derive(Args)]
+struct SolveArgs {
+    /// Files holding boards in the text format, separated by empty lines. Reads stdin if none or `-` is given
+    files: Vec<PathBuf>,
+    /// Solve the board on screen instead
+    #[cfg(feature = "automation")]
+    #[arg(long, conflicts_with = "files")]
+    screen: bool,
+    /// Look for several solutions with depth-first search and print the shortest one,
+    /// shortened further by searching every few moves around each step when cheats are allowed
+    #[arg(long, conflicts_with_all = ["beam_width", "anytime"])]
+    multiple: bool,
+    /// Print the depth-first search progress to stderr after every this many boards
+    #[arg(long, value_name = "BOARDS", conflicts_with_all = ["multiple", "beam_width", "anytime"])]
+    progress: Option<usize>,
+    /// Race depth-first and beam searches with several heuristics, without and with cheats if allowed,
+    /// for at most this many seconds and print the shortest solution found
+    #[arg(long, value_name = "SECONDS", conflicts_with_all = ["multiple", "progress", "beam_width", "search_threads"])]
+    portfolio: Option<f32>,
+    #[command(flatten)]
+    anytime: AnytimeArgs,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    #[command(flatten)]
+    database: DatabaseArgs,
+    #[command(flatten)]
+    solver: SolverArgs,
 }
 
-impl Card {
-    fn from_char(value: char) -> Option<Self> {
-        match value {
-            't' | 'T' => Some(Card::Tits),
-            'k' | 'K' => Some(Card::King),
-            'd' | 'D' => Some(Card::Diva),
-            'v' | 'V' => Some(Card::Viva),
-            '0' | '1' => Some(Card::Ten),
-            '9' => Some(Card::Nine),
-            '8' => Some(Card::Eight),
-            '7' => Some(Card::Seven),
-            '6' => Some(Card::Six),
-            _ => None,
-        }
-    }
+#[derive(Args)]
+struct DatabaseArgs {
+    /// Consult this file of boards known to be winnable or dead and add what the search finds out to it,
+    /// it's created if it doesn't exist yet
+    #[arg(long, value_name = "FILE")]
+    database: Option<PathBuf>,
+}
 
-    fn to_char(&self) -> char {
-        let character = match self {
-            Card::Tits => 'T',
-            Card::King => 'K',
-            Card::Diva => 'D',
-            Card::Viva => 'V',
-            Card::Ten => '0',
-            Card::Nine => '9',
-            Card::Eight => '8',
-            Card::Seven => '7',
-            Card::Six => '6',
+impl DatabaseArgs {
+    fn open(&self) -> Database {
+        let Some(path) = &self.database else {
+            return Database::new();
         };
-        character
+        Database::open(path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            process::exit(1);
+        })
     }
-    
-    fn from_image(image: ImageBuffer<screenshots::image::Rgba<u8>, Vec<u8>>) -> Option<Card> {
-        'card_loop: for card_type in Card::iter() {
-            let card_image = Reader::open(format!("assets/{}.png", card_type.to_char())).unwrap().decode().unwrap();
-            for x in 0..BOX_WIDTH {
-                for y in 0..BOX_HEIGHT {
-                    if &card_image.get_pixel(x, y) != image.get_pixel(x, y) {
-                        continue 'card_loop;
-                    }
-                }
+
+    fn save(&self, database: &Database) {
+        if let Some(path) = &self.database {
+            if let Err(err) = database.save(path) {
+                eprintln!("{}: {}", path.display(), err);
             }
-            return Some(card_type);
         }
-        None
     }
 }
 
-#[derive(Default, Clone)]
-struct Stack {
-    cards: Vec<Card>,
-    collapsed: bool,
-    cheated: bool,
+#[derive(Args)]
+struct AnytimeArgs {
+    /// Keep shortening the solution for at most this many seconds, printing every improvement to stderr
+    #[arg(long, value_name = "SECONDS", conflicts_with_all = ["portfolio", "beam_width", "search_threads"])]
+    anytime: Option<f32>,
+    /// Stop shortening once the solution is at most this many moves
+    #[arg(long, value_name = "MOVES", requires = "anytime")]
+    good_enough: Option<usize>,
+    /// Stop shortening after discovering this many boards
+    #[arg(long, value_name = "BOARDS", requires = "anytime")]
+    node_budget: Option<usize>,
 }
 
-impl Stack {
-    fn highest_orderly_count(&self) -> usize {
-        let len = self.cards.len();
-        if len < 2 {
-            return len;
-        }
-        let mut ret = 1;
-        while self.cards[len-ret] as usize + 1 == self.cards[len-ret-1] as usize {
-            ret += 1;
-            if ret == self.cards.len() {
-                return ret;
+impl AnytimeArgs {
+    fn improve(&self, name: &str, matrix: &Matrix, seconds: f32, solver: &SolverArgs) -> Option<Vec<Move>> {
+        let time = Duration::from_secs_f32(seconds.max(0.0));
+        improve_win(matrix, solver.allow_cheats, &solver.heuristic, solver.limits(), time, self.node_budget, |improvement| {
+            let len = improvement.winner.len();
+            eprintln!("{}: {} moves after {:.02} seconds ({} boards)", name, len, improvement.elapsed.as_secs_f32(), improvement.nodes);
+            if self.good_enough.is_some_and(|good_enough| len <= good_enough) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
             }
-        }
-        ret
-    }
-
-    fn is_orderly(&self, card: Card) -> bool {
-        if self.cards.len() == 0 {
-            return true;
-        }
-        let last_stack_card_index = self.cards.len()-1;
-        let last_stack_card_num = self.cards[last_stack_card_index] as usize;
-        let card_num = card as usize;
-        card_num + 1 == last_stack_card_num
-    }
-
-    fn to_string(&self) -> String {
-        let mut string: String = String::new();
-        string.push('S');
-        for card in &self.cards {
-            string.push(card.to_char());
-        }
-        if self.cheated {
-            string.push('-');
-        }
-        if self.collapsed {
-            string.push('C');
-        }
-        string
+        })
     }
 }
 
-#[derive(PartialEq)]
-enum MoveValidity {
-    ValidNormal,
-    ValidCheat,
-    Invalid,
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// The board and one move per line
+    Text,
+    /// One JSON object per board with the name and solution, including every intermediate board
+    Json,
+    /// One line of moves per solved board, e.g. `3->1x2 0->4x1`
+    Compact,
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum Heuristic {
-    #[allow(unused)]
-    MoveCount,
-    HighestOrder,
-    None,
+#[derive(Args)]
+struct SolverArgs {
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    allow_cheats: bool,
+    /// Give up on a path after this many moves
+    #[arg(long, default_value_t = STEP_LIMIT)]
+    step_limit: usize,
+    /// Give up on a deal after discovering this many board states
+    #[arg(long, default_value_t = PAST_LIMIT)]
+    past_limit: usize,
+    /// Reroll deals whose solution is longer than this
+    #[arg(long, default_value_t = ACCEPTABLE_SOLUTION_LEN)]
+    acceptable_solution_len: usize,
+    /// Move ordering as comma separated `name` or `name:weight` terms,
+    /// e.g. `highest-order` or `collapsed-stacks:-10,orderly-runs:-1,buried-high-cards:0.5`
+    #[arg(long, default_value = "highest-order")]
+    heuristic: Weighted,
+    /// Use beam search keeping this many boards per depth instead of depth-first search
+    #[arg(long, value_name = "WIDTH")]
+    beam_width: Option<usize>,
+    /// Run the depth-first search on this many threads
+    #[arg(long, default_value_t = 1, conflicts_with = "beam_width")]
+    search_threads: usize,
 }
 
-impl Heuristic {
-    // lower score is preferred
-    fn calculate(&self, matrix: &Matrix) -> usize {
-        match self {
-            Heuristic::MoveCount => matrix.valid_moves().len(),
-            Heuristic::HighestOrder => {
-                let mut ret = 40;
-                for stack in &matrix.stacks {
-                    if stack.collapsed {
-                        ret -= 10
-                    } else {
-                        ret -= stack.highest_orderly_count()
-                    }
-                }
-                ret
-            },
-            Heuristic::None => 0,
+impl SolverArgs {
+    fn limits(&self) -> Limits {
+        Limits {
+            step: self.step_limit,
+            past: self.past_limit,
+            acceptable_solution_len: self.acceptable_solution_len,
+            deadline: None,
         }
     }
-}
-
-// 9 different types of cards, 4 sets of cards, 6 columns, 6 rows at the start, 36 cards total
-#[derive(Default)]
-struct Matrix {
-    stacks: [Stack; 6],
-    available_moves: Vec<(Move, Matrix)>,
-    past_moves: Vec<Move>,
-}
 
-impl PartialEq for Matrix {
-    fn eq(&self, other: &Self) -> bool {
-        self.to_string() == other.to_string()
+    /// Consults `database` and adds what the search found out to it.
+    fn find_win(&self, matrix: &Matrix, database: &mut Database) -> Option<Vec<Move>> {
+        let mut past_matrices: HashSet<PackedMatrix> = HashSet::new();
+        let winner_option = self.strategy().find_win(matrix, &mut past_matrices, self.allow_cheats, &self.heuristic, self.limits(), Some(database));
+        database.learn(matrix, self.strategy(), self.allow_cheats, self.limits(), winner_option.as_deref(), &past_matrices);
+        winner_option
     }
-}
 
-impl Eq for Matrix {}
-
-impl Hash for Matrix {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.to_string().hash(state);
+    fn strategy(&self) -> Strategy {
+        match self.beam_width {
+            Some(width) => Strategy::Beam(width),
+            None if self.search_threads > 1 => Strategy::ParallelDepthFirst(self.search_threads),
+            None => Strategy::DepthFirst,
+        }
     }
 }
 
-impl Matrix {
-    fn from_screen() -> Option<Matrix> {
-        let mut matrix: Matrix = Default::default();
+fn main() {
+    // TODO:
+    // - update acceptable solution length
+    //      - figure out average solution length
+    //      - add the time it takes to reroll divided by time per move
+    //      - keep as new acceptable solution length
+    // - play around with depth, past and solution length limits again, new matrix comparison method might've helped
+    // - minimize footprints
+    //      - stop looking for solutions if one under acceptable solution len has been found
 
-        // grab the screen. This is specifically set up for my use-case
-        // aka 3 monitors at FHD, game running on middle monitor with whatever order I've set up
-        let screens = Screen::all().unwrap();
-        let screen = screens[0];
+    let cli = Cli::parse();
+    let start_time = Instant::now();
 
-        for x in 0..6 {
-            for y in 0..6 {
-                let image: ImageBuffer<screenshots::image::Rgba<u8>, Vec<u8>> = screen.capture_area(
-                    OFFSET_H + (x * SPACE_H),
-                    OFFSET_V + (y * SPACE_V),
-                    BOX_WIDTH,
-                    BOX_HEIGHT,
-                ).unwrap();
-                if let Some(card) = Card::from_image(image) {
-                    matrix.stacks[x as usize].cards.push(card);
-                } else {
-                    return None
+    match cli.command {
+        Command::Play { target_wins, dry_run, seed, portfolio, anytime, database: database_args, solver } => {
+            // shared across deals, and across runs with --database
+            let mut database = database_args.open();
+            let mut deals = 0;
+            let find_win = |matrix: &Matrix| {
+                let winner_option = database.winnable().get(matrix, solver.allow_cheats).or_else(|| match (portfolio, anytime.anytime) {
+                    (Some(seconds), _) => race_portfolio(matrix, &solver, seconds).and_then(|result| result.winner),
+                    (None, Some(seconds)) => anytime.improve("deal", matrix, seconds, &solver),
+                    (None, None) => solver.find_win(matrix, &mut database),
+                });
+                if let Some(winner) = &winner_option {
+                    database.insert_win(matrix, winner);
+                }
+                // playing can go on for a long time, don't lose everything when it's interrupted
+                deals += 1;
+                if deals % SAVE_INTERVAL == 0 {
+                    database_args.save(&database);
+                }
+                winner_option
+            };
+            if dry_run {
+                dry_run_wins(target_wins, seed, solver.acceptable_solution_len, find_win);
+            } else {
+                #[cfg(feature = "automation")]
+                automation::loop_wins(target_wins, solver.acceptable_solution_len, find_win);
+                #[cfg(not(feature = "automation"))]
+                {
+                    eprintln!("Playing on screen needs the automation feature, use --dry-run to solve random deals instead");
+                    process::exit(1);
                 }
             }
-        }
-
-        Some(matrix)
-    }
-
-    fn random(rng: &mut impl Rng) -> Matrix { // rng: &mut Rng
-        let mut matrix: Matrix = Default::default();
-        let mut matrix_index = 0;
-        // let mut rng = SmallRng::seed_from_u64(1337);
-
-        for _ in 0..4 {
-            let mut chars = "67890VDKT".to_string();
-            while chars.len() != 0 {
-                let random_index = rng.gen_range(0..chars.len());
-                let character = chars.remove(random_index);
-                matrix.stacks[matrix_index].cards.push(Card::from_char(character).unwrap());
-                matrix_index += 1;
-                matrix_index %= 6;
+            database_args.save(&database);
+        },
+        Command::Solve(args) => {
+            #[cfg(feature = "automation")]
+            if args.screen {
+                match automation::matrix_from_screen() {
+                    Some(matrix) => {
+                        let mut database = args.database.open();
+                        solve("screen".to_string(), matrix, &args, &mut database);
+                        args.database.save(&database);
+                    },
+                    None => println!("No board recognized on screen"),
+                }
+                return;
             }
-        }
-
-        matrix
-    }
-
-    fn check_validity(&self, mov: Move) -> MoveValidity {
-        let Move{from, to, count} = mov;
-        if
-            self.stacks[to].collapsed ||
-            self.stacks[from].collapsed ||
-            self.stacks[to].cheated ||
-            to == from
-        {
-            return MoveValidity::Invalid;
-        }
-        let first_card_index_from = self.stacks[from].cards.len() - count;
-        if count == 1 {
-            if self.stacks[to].is_orderly(self.stacks[from].cards[first_card_index_from]) {
-                return MoveValidity::ValidNormal;
+            let mut database = args.database.open();
+            for (name, matrix) in read_matrices(&args.files) {
+                solve(name, matrix, &args, &mut database);
             }
-            if self.stacks[from].cheated {
-                return MoveValidity::Invalid;
+            args.database.save(&database);
+        },
+        Command::Prove { files, max_nodes, allow_cheats } => {
+            for (name, matrix) in read_matrices(&files) {
+                println!("{}", name);
+                println!("{}", matrix);
+                let mut cheat_options = vec![false];
+                if allow_cheats {
+                    cheat_options.push(true);
+                }
+                for allow_cheats in cheat_options {
+                    let proof = prove(&matrix, allow_cheats, max_nodes);
+                    let verdict = match proof.verdict {
+                        Verdict::Solvable(moves) => format!("solvable in {} moves", moves.len()),
+                        Verdict::Unsolvable => "unsolvable".to_string(),
+                        Verdict::Unknown => "unknown, ran out of nodes".to_string(),
+                    };
+                    let cheats = if allow_cheats { "With cheats" } else { "Without cheats" };
+                    println!("{}: {} ({} nodes)", cheats, verdict, proof.nodes);
+                }
+                println!();
             }
-            return MoveValidity::ValidCheat;
-        }
-        if self.stacks[from].highest_orderly_count() > count {
-            return MoveValidity::Invalid;
-        }
-        if self.stacks[to].cards.is_empty() {
-            return MoveValidity::ValidNormal;
-        }
-        let last_card_index_to = self.stacks[to].cards.len() - 1;
-        if self.stacks[to].cards[last_card_index_to] as usize == self.stacks[from].cards[first_card_index_from] as usize + 1 {
-            return MoveValidity::ValidNormal;
-        }
-        return MoveValidity::Invalid;
-    }
-
-    fn move_stack(&mut self, mov: Move) -> bool {
-        let Move{from, to, count} = mov;
-
-        // validity check
-        let validity: MoveValidity = self.check_validity(mov);
-        if validity == MoveValidity::Invalid {
-            return false;
-        }
-        if validity == MoveValidity::ValidCheat {
-            self.stacks[to].cheated = true;
-        }
-
-        // actual movement
-        let mut moving_cards: Vec<Card> =  vec![];
-
-        self.stacks[from].cheated = false;
-        for _ in 0..count {
-            moving_cards.push(self.stacks[from].cards.pop().unwrap());
-        }
-        for _ in 0..count {
-            self.stacks[to].cards.push(moving_cards.pop().unwrap());
-        }
-
-        // collapsed check
-        if
-            self.stacks[to].highest_orderly_count() == self.stacks[to].cards.len() &&
-            self.stacks[to].cards.len() == 9
-        {
-            self.stacks[to].collapsed = true;
-        }
-
-        return true;
-    }
-
-    fn is_win(&self) -> bool {
-        let mut collapsed_count = 0;
-        for i in 0..6 {
-            if self.stacks[i].collapsed {
-                collapsed_count += 1;
+        },
+        Command::Optimal { files, max_nodes, solver } => {
+            for (name, matrix) in read_matrices(&files) {
+                println!("{}", name);
+                println!("{}", matrix);
+                let mut past_matrices: HashSet<PackedMatrix> = HashSet::new();
+                let winner_option = solver.strategy().find_win(&matrix, &mut past_matrices, solver.allow_cheats, &solver.heuristic, solver.limits(), None);
+                let proof = find_optimal(&matrix, solver.allow_cheats, max_nodes);
+                match (&proof.verdict, winner_option) {
+                    (Verdict::Solvable(moves), Some(winner)) => {
+                        println!("Optimal: {} moves, search: {} moves ({} nodes)", moves.len(), winner.len(), proof.nodes);
+                        println!("{}", format_moves(moves));
+                    },
+                    (Verdict::Solvable(moves), None) => {
+                        println!("Optimal: {} moves, search: no solution ({} nodes)", moves.len(), proof.nodes);
+                        println!("{}", format_moves(moves));
+                    },
+                    (Verdict::Unsolvable, _) => println!("Unsolvable ({} nodes)", proof.nodes),
+                    (Verdict::Unknown, _) => println!("Unknown, ran out of nodes"),
+                }
+                println!();
             }
-        }
-        collapsed_count == 4
-    }
-
-    fn valid_moves(&self) -> Vec<Move> {
-        let mut ret = vec![];
-
-        for from in 0..6 {
-            let highest_orderly_count = self.stacks[from].highest_orderly_count();
-            for count in 1..=highest_orderly_count {
-                for to in 0..6 {
-                    let mov: Move = Move {from, to , count};
-                    if self.check_validity(mov) != MoveValidity::Invalid {
-                        ret.push(mov);
-                    }
+        },
+        Command::Simulate { deals, first_deal, threads, database: database_args, solver } => {
+            let mut database = database_args.open();
+            let deal_range = first_deal..first_deal.saturating_add(deals);
+            let mut cheat_options = vec![false];
+            if solver.allow_cheats {
+                cheat_options.push(true);
+            }
+            for allow_cheats in cheat_options {
+                let database_option = database_args.database.is_some().then_some(&mut database);
+                let results = run_deals(deal_range.clone(), solver.strategy(), allow_cheats, &solver.heuristic, solver.limits(), threads, database_option);
+                if allow_cheats {
+                    println!("With cheats");
+                } else {
+                    println!("Without cheats");
                 }
+                println!("{}", Summary::new(&results));
             }
-        }
-
-        ret
-    }
-
-    fn copy(&self) -> Matrix {
-        let mut matrix: Matrix = Default::default();
-
-        // copy
-        for i in 0..6 {
-            for j in 0..self.stacks[i].cards.len() {
-                matrix.stacks[i].cards.push(self.stacks[i].cards[j]);
+            database_args.save(&database);
+        },
+        #[cfg(feature = "automation")]
+        Command::Capture => {
+            match automation::matrix_from_screen() {
+                Some(matrix) => println!("{}", matrix),
+                None => println!("No board recognized on screen"),
             }
-            matrix.stacks[i].cheated = self.stacks[i].cheated;
-            matrix.stacks[i].collapsed = self.stacks[i].collapsed;
-        }
-        for i in 0..self.past_moves.len() {
-            matrix.past_moves.push(self.past_moves[i]);
-        }
-
-        matrix
-    }
-
-    fn copy_after_move(&self, mov: Move) -> Matrix {
-        let mut matrix: Matrix = self.copy();
-        matrix.move_stack(mov);
-        matrix.past_moves.push(mov);
-        matrix
+        },
+        Command::Replay { file, moves } => {
+            replay(&file, &moves);
+        },
+        Command::Deal { number, count } => {
+            for deal in number..number.saturating_add(count) {
+                if deal != number {
+                    println!();
+                }
+                println!("# deal {}", deal);
+                println!("{}", Matrix::from_deal(deal));
+            }
+        },
+        Command::Benchmark { deals, seed, solver } => {
+            benchmark(deals, solver.strategy(), solver.allow_cheats, &solver.heuristic, seed, solver.limits());
+        },
+        Command::Tune { deals, seed, iterations, threads, solver } => {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let corpus: Vec<Matrix> = (0..deals).map(|_| Matrix::random(&mut rng)).collect();
+            let tuner = Tuner {
+                corpus: &corpus,
+                strategy: solver.strategy(),
+                allow_cheats: solver.allow_cheats,
+                limits: solver.limits(),
+                threads,
+            };
+            let best = tuner.tune(iterations, seed, |iteration, trial, best| {
+                println!(
+                    "{:>4}: cost {:.02}, {}/{} solved, {:.0} nodes  {}  (best {:.02})",
+                    iteration,
+                    trial.cost,
+                    trial.summary.solved,
+                    trial.summary.deals,
+                    trial.summary.mean_nodes(),
+                    trial.heuristic(),
+                    best.cost,
+                );
+            });
+            println!();
+            println!("Best weights: --heuristic {}", best.heuristic());
+            println!("{}", best.summary);
+        },
     }
 
-    fn to_string(&self) -> String {
-        let mut ret = String::new();
-
-        let mut stack_strings: Vec<String> = self.stacks
-            .iter().
-            map(|stack| stack.to_string())
-            .collect();
-
-        stack_strings.sort();
+    eprintln!("Finished after {:.02} seconds", start_time.elapsed().as_secs_f32());
+}
 
-        for stack_string in stack_strings {
-            ret += &stack_string;
+// boards that fail to read or parse are reported and skipped
+fn replay(file: &PathBuf, moves: &str) {
+    let Some((name, matrix)) = read_matrices(std::slice::from_ref(file)).into_iter().next() else {
+        process::exit(1);
+    };
+    let moves = parse_moves(moves).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let mut history = History::with_moves(&matrix, &moves).unwrap_or_else(|(index, error)| {
+        eprintln!("{}: move {} ({}) is invalid: {}", name, index + 1, moves[index], error);
+        process::exit(1);
+    });
+
+    print_step(&history);
+    for line in io::stdin().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match line.trim() {
+            "" | "n" => {
+                history.redo();
+            },
+            "p" => {
+                history.undo();
+            },
+            "q" => break,
+            step => match step.parse() {
+                Ok(step) => history.jump_to(step),
+                Err(_) => {
+                    eprintln!("Expected n, p, q or a step number");
+                    continue;
+                },
+            },
         }
-
-        ret
+        print_step(&history);
     }
+}
 
-    fn save_moves(&mut self, allow_cheats: bool, heuristic: Heuristic) {
-        self.available_moves = self
-            .valid_moves()
-            .iter()
-            .map(|mov| (*mov, self.copy_after_move(*mov)))
-            .filter(|(mov, _)| allow_cheats || self.check_validity(*mov) != MoveValidity::ValidCheat)
-            .collect();
-        
-        if heuristic != Heuristic::None {
-            self.available_moves.sort_by(
-                |(_, a), (_, b)| {
-                    let ah = heuristic.calculate(a);
-                    let bh = heuristic.calculate(b);
-                    ah.cmp(&bh)
-                }
-            );
-        }
+fn print_step(history: &History) {
+    match history.matrix().past_moves.last() {
+        Some(mov) => println!("Step {}/{}: {}", history.step(), history.len(), mov),
+        None => println!("Step 0/{}", history.len()),
     }
+    println!("{}", history.matrix());
+}
 
-    fn prune(&mut self, past_matrices: &mut HashSet<Matrix>) {
-        for i in (0..self.available_moves.len()).rev() {
-            if past_matrices.contains(&self.available_moves[i].1) {
-                self.available_moves.remove(i);
-            } else {
-                past_matrices.insert(self.available_moves[i].1.copy());
-            }
-        }
-    }
+fn read_matrices(files: &[PathBuf]) -> Vec<(String, Matrix)> {
+    let stdin_path = PathBuf::from("-");
+    let paths = if files.is_empty() {
+        std::slice::from_ref(&stdin_path)
+    } else {
+        files
+    };
 
-    fn past_matrices(&self, start_matrix: Matrix) -> Vec<Matrix> {
-        let mut ret: Vec<Matrix> = vec![];
-        let mut last_matrix = start_matrix.copy();
-        for mov in &self.past_moves {
-            last_matrix = last_matrix.copy_after_move(*mov);
-            ret.push(last_matrix.copy());
+    let mut ret = vec![];
+    for path in paths {
+        let (name, text_result) = if *path == stdin_path {
+            let mut text = String::new();
+            ("stdin".to_string(), io::stdin().read_to_string(&mut text).map(|_| text))
+        } else {
+            (path.display().to_string(), fs::read_to_string(path))
+        };
+        let text = match text_result {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("{}: {}", name, err);
+                continue;
+            },
+        };
+        for (index, matrix_result) in parse_matrices(&text).into_iter().enumerate() {
+            let board_name = format!("{} #{}", name, index + 1);
+            match matrix_result {
+                Ok(matrix) => ret.push((board_name, matrix)),
+                Err(err) => eprintln!("{}: {}", board_name, err),
+            }
         }
-        ret
     }
+    ret
 }
 
-#[derive(Clone, Copy, Debug)]
-struct Move {
-    from: usize,
-    to: usize,
-    count: usize,
+#[derive(Serialize)]
+struct SolveOutput {
+    name: String,
+    solution: Option<Solution>,
+    /// Portfolio entry that found the solution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strategy: Option<String>,
 }
 
-fn main() {
-    // TODO:
-    // - enable brute_force to also find "wins" by finding a winnable state
-    //      - if winnable_state.moves_left + current_matrix.past_moves < max_len: return it
-    //      - need a structure to hold winnable states and their moves_left
-    //      - brute_force (and by extension optimize_solution) would need to return Vec<Move> instead
-    // - sort stacks alphabetically when generating matrix string, hash, and comparison
-    //      - this should prevent equivalent gamestates with different order of stacks which doesn't matter
-    // - update acceptable solution length
-    //      - figure out average solution length
-    //      - add the time it takes to reroll divided by time per move
-    //      - keep as new acceptable solution length
-    // - play around with depth, past and solution length limits again, new matrix comparison method might've helped
-    // - minimize footprints
-    //      - past_matrices hashset only needs to have strings, not the whole structure
-    //      - remove Move from available_moves vec
-    //      - stop looking for solutions if one under acceptable solution len has been found
-    // - add CLI
-
-    let start_time = Instant::now();
-
-    // let mut matrix = Matrix::random();
-    // let start_matrix = matrix.copy();
-
-    // let mut winners: Vec<Matrix> = vec![];
-    // let mut past_matrices: HashSet<Matrix> = HashSet::new();
-    // while past_matrices.len() < PAST_LIMIT {
-    //     if let Some(winner) = find_win(&mut matrix, &mut past_matrices, true) {
-    //         winners.push(winner);
-    //     }
-    // }
-    // if !winners.is_empty() {
-    //     winners.sort_by(|a, b| a.past_moves.len().cmp(&b.past_moves.len()));
-    //     println!("Best before opti: {}", winners[0].past_moves.len());
-    //     optimize_solutions(start_matrix, &winners);
-    // }
-
-    loop_wins(7, true, false);
-
-    println!("Finished after {:.02} seconds", start_time.elapsed().as_secs_f32());
+fn race_portfolio(matrix: &Matrix, solver: &SolverArgs, seconds: f32) -> Option<EntryResult> {
+    race(matrix, &default_entries(solver.allow_cheats), Duration::from_secs_f32(seconds.max(0.0)), solver.limits()).into_best()
 }
 
-// This expects sorted winner matrices
-#[allow(dead_code)]
-fn optimize_solutions(start_matrix: Matrix, winner_matrices: &Vec<Matrix>) -> Matrix {
-    let winner_matrices_past_matrices: Vec<Vec<Matrix>> = winner_matrices
-        .iter()
-        .map(|winner_matrix| {
-            winner_matrix.past_matrices(start_matrix.copy())
+fn solve(name: String, matrix: Matrix, args: &SolveArgs, database: &mut Database) {
+    let SolveArgs { multiple, progress, portfolio, anytime, format, solver, .. } = args;
+    let mut strategy_option: Option<String> = None;
+    let winner_option = if *multiple {
+        let winners = find_multiple_wins(&matrix, solver.allow_cheats, &solver.heuristic, solver.limits(), database.winnable_mut());
+        match winners.first() {
+            // shortening them needs cheats, without them there's only ever one
+            Some(_) if solver.allow_cheats => Some(optimize_solutions(&matrix, &winners, database.winnable_mut())),
+            first => first.cloned(),
+        }
+    } else if let Some(interval) = progress {
+        let mut search = Search::new(&matrix, solver.allow_cheats, &solver.heuristic, solver.limits());
+        search.run_with_progress((*interval).max(1), |progress| {
+            eprintln!("{}: {} boards, {} moves deep", name, progress.nodes, progress.depth);
         })
-        .collect();
-    let mut best_matrix = winner_matrices[0].copy();
-
-    let starting_cutoff = 2; // I could probably start way earlier, there's a lot of goofing near the end of almost every solve
-
-    for winner_matrix_past_matrices in winner_matrices_past_matrices {
-        for i in (0..winner_matrix_past_matrices.len()-starting_cutoff).rev() {
-            // TODO: prevent this from firing if it'd take too long (if the bruteforce depth is too big)
-            let mut past_matrix = winner_matrix_past_matrices[i].copy();
-            let brute_force_depth = best_matrix.past_moves.len() as isize - past_matrix.past_moves.len() as isize;
-            if brute_force_depth > 6 {break;} // brute_force could take too long, stick to 6 or 7 (max 7 or 8)
-            if brute_force_depth < 0 {continue;} // brute_force would immediately end, save some performance
-
-
-            // run bruteforce
-            let mut discovered_matrices: HashSet<Matrix> = HashSet::new();
-            let better_matrix_option = brute_force(&mut past_matrix, best_matrix.past_moves.len(), &mut discovered_matrices);
-            if let Some(better_matrix) = better_matrix_option {
-                if better_matrix.past_moves.len() < best_matrix.past_moves.len() {
-                    best_matrix = better_matrix;
-                }
-            }
-        }
-    }
-
-    best_matrix
-}
-
-fn brute_force(matrix: &mut Matrix, max_len: usize, discovered_matrices: &mut HashSet<Matrix>) -> Option<Matrix> {
-    discovered_matrices.insert(matrix.copy());
-    if matrix.past_moves.len() >= max_len {
-        return None;
-    }
-    matrix.save_moves(true, Heuristic::None); // we don't need to find optimizations for non-cheated runs, only one is good enough anyway
-    if matrix.available_moves.is_empty() {
-        if matrix.is_win() {
-            return Some(matrix.copy());
-        } else {
-            return None
-        }
+    } else if let Some(seconds) = anytime.anytime {
+        anytime.improve(&name, &matrix, seconds, solver)
+    } else if let Some(seconds) = portfolio {
+        race_portfolio(&matrix, solver, *seconds).and_then(|result| {
+            strategy_option = Some(result.entry.to_string());
+            result.winner
+        })
+    } else {
+        solver.find_win(&matrix, database)
+    };
+    if let Some(winner) = &winner_option {
+        database.insert_win(&matrix, winner);
     }
-    let mut best_matrix_option: Option<Matrix> = None;
-    for (_, next_matrix) in &matrix.available_moves {
-        if let Some(winner) = brute_force(&mut next_matrix.copy(), max_len, discovered_matrices) {
-            if let Some(best_matrix) = &best_matrix_option {
-                if best_matrix.past_moves.len() > winner.past_moves.len() {
-                    best_matrix_option = Some(winner);
-                }
-            } else {
-                best_matrix_option = Some(winner);
+    let solution_option = winner_option.and_then(|winner| match Solution::new(&matrix, &winner) {
+        Ok(solution) => Some(solution),
+        Err((index, error)) => {
+            eprintln!("{}: move {} of the solution is invalid: {}", name, index + 1, error);
+            None
+        },
+    });
+
+    match format {
+        OutputFormat::Text => {
+            println!("{}", name);
+            println!("{}", matrix);
+            match solution_option {
+                Some(solution) => {
+                    println!("Solution found: {} moves, {} cheats", solution.len(), solution.cheat_count());
+                    if let Some(strategy) = &strategy_option {
+                        println!("Found by {}", strategy);
+                    }
+                    for step in &solution.steps {
+                        if step.cheat {
+                            println!("{} (cheat)", step.mov);
+                        } else {
+                            println!("{}", step.mov);
+                        }
+                    }
+                },
+                None => println!("No solution found"),
             }
-        }
+            println!();
+        },
+        OutputFormat::Json => {
+            let output = SolveOutput { name, solution: solution_option, strategy: strategy_option };
+            println!("{}", serde_json::to_string(&output).unwrap());
+        },
+        OutputFormat::Compact => {
+            match solution_option {
+                Some(solution) => println!("{}", format_moves(&solution.moves())),
+                None => eprintln!("{}: No solution found", name),
+            }
+        },
     }
-    best_matrix_option
 }
 
-fn loop_wins(target_wins: usize, allow_cheats: bool, dry_run: bool) {
-    let mut rng = SmallRng::seed_from_u64(1337);
-    let mut enigo = Enigo::new();
+fn dry_run_wins(target_wins: usize, seed: u64, acceptable_solution_len: usize, mut find_win: impl FnMut(&Matrix) -> Option<Vec<Move>>) {
+    let mut rng = SmallRng::seed_from_u64(seed);
     let mut iter_count = 0;
-    let mut matrix_option = if dry_run {
-        Matrix::from_screen()
-    } else {
-        None
-    };
+    let mut deal_count = 0;
     while iter_count < target_wins {
-        let mut matrix = if dry_run {
-            Matrix::random(&mut rng)
-        } else {
-            if let Some(matrix) = &matrix_option {
-                matrix.copy()
-            } else {
-                // focus window
-                enigo.mouse_move_to(
-                    1920 + OFFSET_H - SPACE_H,
-                    OFFSET_V - SPACE_V,
-                );
-                sleep(Duration::from_millis(100));
-                enigo.mouse_down(enigo::MouseButton::Left);
-                sleep(Duration::from_millis(50));
-                enigo.mouse_up(enigo::MouseButton::Left);
-                sleep(Duration::from_millis(100));
-
-                // click new game
-                enigo.mouse_move_to(
-                    1920 + (1920/2),
-                    1080 - 40,
-                );
-                sleep(Duration::from_millis(100));
-                enigo.mouse_down(enigo::MouseButton::Left);
-                sleep(Duration::from_millis(50));
-                enigo.mouse_up(enigo::MouseButton::Left);
-                
-                // wait for game to be set up
-                let mut matrix_option = Matrix::from_screen();
-                while matrix_option.is_none() {
-                    sleep(Duration::from_millis(1500));
-                    matrix_option = Matrix::from_screen();
-                }
-                matrix_option.unwrap()
-            }
-        };
-        
-        // find solutions
-        // let mut winners = find_multiple_wins(matrix.copy(), allow_cheats, Heuristic::HighestOrder);
-        // let winner_option = find_multiple_wins(matrix.copy(), allow_cheats, Heuristic::HighestOrder).into_iter().next();
-        // println!("Best solution found: {} moves", winners[0].past_moves.len());
-
-        let mut past_matrices: HashSet<Matrix> = HashSet::new();
-        let winner_option = find_win(&mut matrix, &mut past_matrices, allow_cheats, Heuristic::HighestOrder);
-        
-        // check for optimizations and different solutions or retry
-        // if winners.is_empty() || (allow_cheats && winners[0].past_moves.len() > ACCEPTABLE_SOLUTION_LEN) {
-        //     if winners.is_empty() {
-        //         // no winners, try finding wins with a different heuristic
-        //         winners = find_multiple_wins(matrix.copy(), allow_cheats, Heuristic::MoveCount);
-        //         if winners.is_empty() || (allow_cheats && winners[0].past_moves.len() > ACCEPTABLE_SOLUTION_LEN) {
-        //             if winners.is_empty() {
-        //                 println!("No solution found, reattempting...");
-        //             } else {
-        //                 println!("Secondary solution over {} moves, reattempting...", ACCEPTABLE_SOLUTION_LEN);
-        //             }
-        //             matrix_option = None;
-        //             continue;
-        //         } else {
-        //             println!("Found a secondary solution!");
-        //         }
-        //     } else {
-        //         // solution too long, try to optimize it
-        //         // made redundant by heuristic improvements, keeping for testing purposes
-        //         let winner = optimize_solutions(matrix.copy(), &winners);
-        //         winners = vec![winner];
-        //         if allow_cheats && winners[0].past_moves.len() > ACCEPTABLE_SOLUTION_LEN {
-        //             println!("Couldn't optimize primary solution, reattempting...");
-        //             matrix_option = None;
-        //             continue;
-        //         } else {
-        //             println!("Optimized primary solution!");
-        //         }
-        //     }
-        // }
-
-        // execute best solution
-        // println!("Executing solution: {} moves", winners[0].past_moves.len());
-        if let Some(winner) = winner_option {
-            if winner.past_moves.len() <= ACCEPTABLE_SOLUTION_LEN {
-                if !dry_run {
-                    execute_moves(&mut matrix, &winner.past_moves);
-                }
+        let matrix = Matrix::random(&mut rng);
+        deal_count += 1;
+        if let Some(winner) = find_win(&matrix) {
+            if winner.len() <= acceptable_solution_len {
                 iter_count += 1;
             }
         }
-        matrix_option = None;
     }
+    println!("Won {} games out of {} deals", iter_count, deal_count);
 }
 
-fn find_multiple_wins(matrix: Matrix, allow_cheats: bool, heuristic: Heuristic) -> Vec<Matrix> {
-    let mut winners: Vec<Matrix> = vec![];
-    let mut past_matrices: HashSet<Matrix> = HashSet::new();
-    past_matrices.insert(matrix.copy());
-
-    while past_matrices.len() < PAST_LIMIT {
-        // println!("finding win");
-        if let Some(winner) = find_win(&mut matrix.copy(), &mut past_matrices, allow_cheats, heuristic) {
-            // println!("Found solution: {} moves", winner.past_moves.len());
-            winners.push(winner);
-            if !allow_cheats {
-                break;
+fn benchmark(deals: usize, strategy: Strategy, allow_cheats: bool, heuristic: &dyn Heuristic, seed: u64, limits: Limits) {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut solved = 0;
+    let mut acceptable = 0;
+    let mut total_moves = 0;
+    let mut total_time = Duration::ZERO;
+    for _ in 0..deals {
+        let matrix = Matrix::random(&mut rng);
+        let mut past_matrices: HashSet<PackedMatrix> = HashSet::new();
+        let deal_start_time = Instant::now();
+        let winner_option = strategy.find_win(&matrix, &mut past_matrices, allow_cheats, heuristic, limits, None);
+        total_time += deal_start_time.elapsed();
+        if let Some(winner) = winner_option {
+            solved += 1;
+            total_moves += winner.len();
+            if winner.len() <= limits.acceptable_solution_len {
+                acceptable += 1;
             }
         }
     }
-
-    winners.sort_by(|a, b| a.past_moves.len().cmp(&b.past_moves.len()));
-    winners
-}
-
-fn execute_moves(matrix: &mut Matrix, moves: &Vec<Move>) {
-    let mut enigo = Enigo::new();
-
-    // focus window but don't pick a card if window already focused
-    enigo.mouse_move_to(
-        1920 + OFFSET_H - SPACE_H,
-        OFFSET_V - SPACE_V,
-    );
-    sleep(Duration::from_millis(100));
-    enigo.mouse_down(enigo::MouseButton::Left);
-    sleep(Duration::from_millis(50));
-    enigo.mouse_up(enigo::MouseButton::Left);
-    sleep(Duration::from_millis(100));
-
-    for mov in moves {
-        let y_from = matrix.stacks[mov.from].cards.len() - mov.count;
-        enigo.mouse_move_to(
-            1920 + OFFSET_H + (mov.from as i32 * SPACE_H),
-            OFFSET_V + (y_from.max(0) as i32 * SPACE_V),
-        );
-        enigo.mouse_down(enigo::MouseButton::Left);
-        sleep(Duration::from_millis(50));
-        enigo.mouse_up(enigo::MouseButton::Left);
-
-        sleep(Duration::from_millis(50));
-
-        let y_to = (matrix.stacks[mov.to].cards.len() as i32 - 1).max(0) as usize;
-        enigo.mouse_move_to(
-            1920 + OFFSET_H + (mov.to as i32 * SPACE_H),
-            OFFSET_V + (y_to as i32 * SPACE_V),
-        );
-        enigo.mouse_down(enigo::MouseButton::Left);
-        sleep(Duration::from_millis(50));
-        enigo.mouse_up(enigo::MouseButton::Left);
-
-        sleep(Duration::from_millis(100));
-
-        matrix.move_stack(*mov);
-    }
-}
-
-fn find_win(matrix: &mut Matrix, past_matrices: &mut HashSet<Matrix>, allow_cheats: bool, heuristic: Heuristic) -> Option<Matrix> {
-    if allow_cheats && past_matrices.len() > PAST_LIMIT {
-        return None;
+    println!("Solved {}/{} deals ({} within {} moves)", solved, deals, acceptable, limits.acceptable_solution_len);
+    if solved > 0 {
+        println!("Average solution length: {:.02} moves", total_moves as f32 / solved as f32);
     }
-    // println!("{}", matrix.to_string());
-
-    matrix.save_moves(allow_cheats, heuristic);
-    matrix.prune(past_matrices);
-
-    if matrix.available_moves.is_empty() {
-        if matrix.is_win() {
-            return Some(matrix.copy());
-        } else {
-            return None;
-        }
-    } else {
-        if allow_cheats && matrix.past_moves.len() > STEP_LIMIT {
-            return None;
-        }
-        for i in 0..matrix.available_moves.len() {
-            let result = find_win(&mut matrix.available_moves[i].1, past_matrices, allow_cheats, heuristic);
-            if result.is_none() {
-                continue;
-            } else {
-                return result;
-            }
-        }
+    if deals > 0 {
+        println!("Average time per deal: {:.04} seconds", total_time.as_secs_f32() / deals as f32);
     }
-    None
 }
@@ -0,0 +1,204 @@
+use std::{fmt, hash::Hash, str::FromStr};
+use rand::Rng;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{card::Card, rules::{MoveError, Rules}, stack::Stack};
+
+/// Moves `count` cards from the top of stack `from` onto stack `to`.
+///
+/// Written as `from->toxcount` in the compact format, e.g. `3->1x2`, with stacks counted from 0.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    pub count: usize,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}->{}x{}", self.from, self.to, self.count)
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let (from, rest) = s.trim().split_once("->")?;
+            let (to, count) = rest.split_once('x')?;
+            let mov = Move {
+                from: from.parse().ok()?,
+                to: to.parse().ok()?,
+                count: count.parse().ok()?,
+            };
+            (mov.from < 6 && mov.to < 6 && mov.count > 0).then_some(mov)
+        };
+        parse().ok_or_else(|| format!("invalid move '{}', expected e.g. 3->1x2", s))
+    }
+}
+
+/// Result of checking a [`Move`] against a board.
+#[derive(PartialEq, Debug)]
+pub enum MoveValidity {
+    ValidNormal,
+    /// A single card placed out of order, the target stack becomes cheated.
+    ValidCheat,
+    Invalid,
+}
+
+/// A game board together with the moves that led to it.
+///
+/// Two matrices are equal when their stacks are, regardless of the column order and history.
+// 9 different types of cards, 4 sets of cards, 6 columns, 6 rows at the start, 36 cards total
+#[derive(Default)]
+pub struct Matrix {
+    pub stacks: [Stack; 6],
+    pub past_moves: Vec<Move>,
+}
+
+impl PartialEq for Matrix {
+    fn eq(&self, other: &Self) -> bool {
+        self.pack() == other.pack()
+    }
+}
+
+impl Eq for Matrix {}
+
+impl Hash for Matrix {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.pack().hash(state);
+    }
+}
+
+// only the stacks, the history is what solutions are made of
+impl Serialize for Matrix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.stacks.serialize(serializer)
+    }
+}
+
+impl Matrix {
+    /// Deals the 36 cards randomly across the six stacks.
+    pub fn random(rng: &mut impl Rng) -> Matrix {
+        let mut matrix: Matrix = Default::default();
+        let mut matrix_index = 0;
+
+        for _ in 0..4 {
+            let mut chars = "67890VDKT".to_string();
+            while !chars.is_empty() {
+                let random_index = rng.gen_range(0..chars.len());
+                let character = chars.remove(random_index);
+                matrix.stacks[matrix_index].cards.push(Card::from_char(character).unwrap());
+                matrix_index += 1;
+                matrix_index %= 6;
+            }
+        }
+
+        matrix
+    }
+
+    /// Whether `mov` is a normal move or a cheat on this board, see [`rules`](crate::rules).
+    pub fn check_validity(&self, mov: Move) -> MoveValidity {
+        Rules::WITH_CHEATS.validity(self, mov)
+    }
+
+    /// Makes the move and records it in `past_moves`, collapsing the target stack when it completes.
+    ///
+    /// Invalid moves leave the board untouched.
+    pub fn try_apply(&mut self, mov: Move) -> Result<(), MoveError> {
+        self.move_stack(mov)?;
+        self.past_moves.push(mov);
+        Ok(())
+    }
+
+    /// Like [`Matrix::try_apply`], without recording the move in `past_moves`.
+    pub fn move_stack(&mut self, mov: Move) -> Result<(), MoveError> {
+        Rules::WITH_CHEATS.try_apply(self, mov).map(|_| ())
+    }
+
+    /// The game is won once four stacks have collapsed.
+    pub fn is_win(&self) -> bool {
+        Rules::is_win(self)
+    }
+
+    /// All valid moves, cheats included.
+    pub fn valid_moves(&self) -> Vec<Move> {
+        let mut ret = vec![];
+
+        for from in 0..6 {
+            let highest_orderly_count = self.stacks[from].highest_orderly_count();
+            for count in 1..=highest_orderly_count {
+                for to in 0..6 {
+                    let mov: Move = Move {from, to , count};
+                    if self.check_validity(mov) != MoveValidity::Invalid {
+                        ret.push(mov);
+                    }
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Copies the stacks and move history.
+    pub fn copy(&self) -> Matrix {
+        let mut matrix: Matrix = Default::default();
+
+        // copy
+        for i in 0..6 {
+            for j in 0..self.stacks[i].cards.len() {
+                matrix.stacks[i].cards.push(self.stacks[i].cards[j]);
+            }
+            matrix.stacks[i].cheated = self.stacks[i].cheated;
+            matrix.stacks[i].collapsed = self.stacks[i].collapsed;
+        }
+        for i in 0..self.past_moves.len() {
+            matrix.past_moves.push(self.past_moves[i]);
+        }
+
+        matrix
+    }
+
+    /// A copy with `mov` made, for moves known to be valid like those of [`Matrix::valid_moves`].
+    ///
+    /// Panics if `mov` is invalid, moves from elsewhere go through [`Matrix::try_apply`].
+    pub fn copy_after_move(&self, mov: Move) -> Matrix {
+        let mut matrix: Matrix = self.copy();
+        if let Err(error) = matrix.try_apply(mov) {
+            panic!("invalid move {}: {}", mov, error);
+        }
+        matrix
+    }
+
+    // stacks are sorted so that boards differing only in column order compare equal
+    pub fn canonical_string(&self) -> String {
+        let mut ret = String::new();
+
+        let mut stack_strings: Vec<String> = self.stacks
+            .iter()
+            .map(|stack| stack.to_string())
+            .collect();
+
+        stack_strings.sort();
+
+        for stack_string in stack_strings {
+            ret += &stack_string;
+        }
+
+        ret
+    }
+
+    /// Makes `moves` one after another, returning every board along the way, starting with a copy of this one.
+    ///
+    /// Fails on the first invalid move, with its index in `moves`.
+    pub fn replay(&self, moves: &[Move]) -> Result<Vec<Matrix>, (usize, MoveError)> {
+        let mut ret: Vec<Matrix> = vec![self.copy()];
+        for (index, mov) in moves.iter().enumerate() {
+            let mut next_matrix = ret[ret.len() - 1].copy();
+            next_matrix.try_apply(*mov).map_err(|error| (index, error))?;
+            ret.push(next_matrix);
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,186 @@
+//! A* search for the shortest solution.
+//!
+//! Winning means having all 36 cards in four collapsed stacks, which hold 32 pairs of cards
+//! sitting directly on their successor. A move joins the moved cards to at most one card,
+//! so it can create at most one such pair, and the number of pairs still missing is a lower bound
+//! on the number of moves left. A collapsed stack always holds its 8 pairs and the top run of any
+//! other stack holds `highest_orderly_count() - 1` of them, buried runs count as well.
+//!
+//! With that bound A* only returns once no shorter solution can exist.
+
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap}};
+
+use crate::{matrix::{Matrix, Move, MoveValidity}, packed::PackedMatrix, prover::{Proof, Verdict}, rules::Rules};
+
+const WIN_PAIRS: usize = 32;
+
+/// Minimum number of moves needed to win from `matrix`, see the [module docs](crate::optimal).
+pub fn lower_bound(matrix: &Matrix) -> usize {
+    let mut pairs = 0;
+    for stack in &matrix.stacks {
+        if stack.collapsed {
+            pairs += 8;
+            continue;
+        }
+        pairs += stack.cards
+            .windows(2)
+            .filter(|pair| pair[0] as usize == pair[1] as usize + 1)
+            .count();
+    }
+    WIN_PAIRS.saturating_sub(pairs)
+}
+
+struct Node {
+    matrix: Matrix,
+    moves_taken: usize,
+    parent: Option<(usize, Move)>,
+}
+
+/// Finds a shortest solution of `matrix`, giving up with [`Verdict::Unknown`] after expanding `max_nodes` boards if set.
+///
+/// [`Verdict::Solvable`] holds a solution no other is shorter than.
+pub fn find_optimal(matrix: &Matrix, allow_cheats: bool, max_nodes: Option<usize>) -> Proof {
+    let rules = Rules::new(allow_cheats);
+    let mut start_matrix = matrix.copy();
+    start_matrix.past_moves.clear();
+
+    let mut best_moves_taken: HashMap<PackedMatrix, usize> = HashMap::new();
+    best_moves_taken.insert(start_matrix.pack(), 0);
+    // (moves taken + lower bound, lower bound, node index), ties go to nodes closer to a win
+    let mut open: BinaryHeap<Reverse<(usize, usize, usize)>> = BinaryHeap::new();
+    open.push(Reverse((lower_bound(&start_matrix), lower_bound(&start_matrix), 0)));
+    let mut nodes: Vec<Node> = vec![Node {
+        matrix: start_matrix,
+        moves_taken: 0,
+        parent: None,
+    }];
+    let mut expanded = 0;
+
+    while let Some(Reverse((_, _, index))) = open.pop() {
+        let node = &nodes[index];
+        // a shorter way to this board was found after this entry was queued
+        if best_moves_taken[&node.matrix.pack()] < node.moves_taken {
+            continue;
+        }
+        if node.matrix.is_win() {
+            return Proof {
+                verdict: Verdict::Solvable(moves_to(&nodes, index)),
+                nodes: expanded,
+            };
+        }
+        expanded += 1;
+        if max_nodes.is_some_and(|max_nodes| expanded > max_nodes) {
+            return Proof {
+                verdict: Verdict::Unknown,
+                nodes: expanded,
+            };
+        }
+
+        let moves_taken = node.moves_taken + 1;
+        let mut children: Vec<Node> = vec![];
+        for mov in node.matrix.distinct_moves() {
+            if rules.validity(&node.matrix, mov) == MoveValidity::Invalid {
+                continue;
+            }
+            let mut next_matrix = node.matrix.copy();
+            next_matrix.move_stack(mov).expect("moves from distinct_moves are valid");
+            let key = next_matrix.pack();
+            if best_moves_taken.get(&key).is_some_and(|best| *best <= moves_taken) {
+                continue;
+            }
+            best_moves_taken.insert(key, moves_taken);
+            children.push(Node {
+                matrix: next_matrix,
+                moves_taken,
+                parent: Some((index, mov)),
+            });
+        }
+        for child in children {
+            let bound = lower_bound(&child.matrix);
+            open.push(Reverse((moves_taken + bound, bound, nodes.len())));
+            nodes.push(child);
+        }
+    }
+
+    Proof {
+        verdict: Verdict::Unsolvable,
+        nodes: expanded,
+    }
+}
+
+fn moves_to(nodes: &[Node], index: usize) -> Vec<Move> {
+    let mut moves: Vec<Move> = vec![];
+    let mut current = index;
+    while let Some((parent, mov)) = nodes[current].parent {
+        moves.push(mov);
+        current = parent;
+    }
+    moves.reverse();
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+    use super::*;
+    use crate::{cache::WinnableCache, heuristic::HighestOrder, search::{brute_force_until, find_win, Limits}};
+
+    // deals winnable without cheats within a few thousand boards
+    const DEALS: [u64; 3] = [1, 4, 11];
+    // exhaustive search explores every sequence shorter than this
+    const BRUTE_FORCE_LEN: usize = 5;
+
+    fn optimal_len(matrix: &Matrix, allow_cheats: bool) -> Option<usize> {
+        match find_optimal(matrix, allow_cheats, None).verdict {
+            Verdict::Solvable(moves) => {
+                let boards = matrix.replay(&moves).unwrap();
+                assert!(boards.last().unwrap().is_win());
+                Some(moves.len())
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_near_the_end() {
+        let mut rng = SmallRng::seed_from_u64(9);
+        let mut compared = 0;
+        for deal in DEALS {
+            let matrix = Matrix::from_deal(deal);
+            let winner = find_win(&matrix, &mut HashSet::new(), false, &HighestOrder, Limits::default(), None).unwrap();
+            let boards = matrix.replay(&winner).unwrap();
+            for moves_left in 2..=4 {
+                // a detour, so the rest of the solution isn't necessarily the shortest anymore
+                let mut board = boards[boards.len() - 1 - moves_left].copy();
+                if let Some(mov) = board.valid_moves().choose(&mut rng).copied() {
+                    board.try_apply(mov).unwrap();
+                }
+                for allow_cheats in [false, true] {
+                    let shortest = brute_force_until(&board, BRUTE_FORCE_LEN, &mut HashMap::new(), allow_cheats, &WinnableCache::new(), None, None)
+                        .map(|moves| moves.len());
+                    let optimal = optimal_len(&board, allow_cheats);
+                    match shortest {
+                        Some(len) => assert_eq!(optimal, Some(len), "deal {}, {} moves left:\n{}", deal, moves_left, board),
+                        None => assert!(optimal.is_none_or(|len| len >= BRUTE_FORCE_LEN)),
+                    }
+                    compared += 1;
+                }
+            }
+        }
+        assert!(compared > 0);
+    }
+
+    #[test]
+    fn never_longer_than_depth_first() {
+        for deal in DEALS {
+            let matrix = Matrix::from_deal(deal);
+            let winner = find_win(&matrix, &mut HashSet::new(), false, &HighestOrder, Limits::default(), None).unwrap();
+            let optimal = optimal_len(&matrix, false).unwrap();
+            assert!(optimal <= winner.len(), "deal {}: {} moves, depth-first found {}", deal, optimal, winner.len());
+            assert!(lower_bound(&matrix) <= optimal);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Compact board representation for remembering which boards the search has already seen.
+//!
+//! A [`PackedMatrix`] fits a board in three `u64`s: 4 bits for each of the 36 cards,
+//! 6 bits for the length of each stack and 2 bits for the flags of each stack.
+//! Stacks are sorted before packing, so boards differing only in column order pack the same,
+//! and the move history isn't kept.
+
+use std::cmp::Ordering;
+
+use crate::{card::Card, matrix::Matrix, stack::Stack};
+
+const CARD_BITS: usize = 4;
+const LEN_BITS: usize = 6;
+const FLAG_BITS: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PackedMatrix([u64; 3]);
+
+struct BitWriter {
+    words: [u64; 3],
+    position: usize,
+}
+
+impl BitWriter {
+    // the lowest `bits` of `value`, which has no higher bits set
+    fn write(&mut self, value: u64, bits: usize) {
+        let (word, offset) = (self.position / 64, self.position % 64);
+        self.words[word] |= value << offset;
+        // split across two words
+        if offset + bits > 64 {
+            self.words[word + 1] |= value >> (64 - offset);
+        }
+        self.position += bits;
+    }
+}
+
+struct BitReader<'a> {
+    words: &'a [u64; 3],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: usize) -> u64 {
+        let (word, offset) = (self.position / 64, self.position % 64);
+        let mut value = self.words[word] >> offset;
+        if offset + bits > 64 {
+            value |= self.words[word + 1] << (64 - offset);
+        }
+        self.position += bits;
+        value & ((1 << bits) - 1)
+    }
+}
+
+pub(crate) fn compare_stacks(a: &Stack, b: &Stack) -> Ordering {
+    a.cards
+        .iter()
+        .map(|card| *card as usize)
+        .cmp(b.cards.iter().map(|card| *card as usize))
+        .then(a.cheated.cmp(&b.cheated))
+        .then(a.collapsed.cmp(&b.collapsed))
+}
+
+impl PackedMatrix {
+    /// The packed bits, for storing boards outside the program.
+    pub fn to_words(self) -> [u64; 3] {
+        self.0
+    }
+
+    /// Bits previously returned by [`PackedMatrix::to_words`].
+    pub fn from_words(words: [u64; 3]) -> PackedMatrix {
+        PackedMatrix(words)
+    }
+
+    /// Packs the stacks of `matrix` in the order of `columns`, which have to be its [`Matrix::canonical_columns`].
+    pub(crate) fn from_columns(matrix: &Matrix, columns: &[usize; 6]) -> PackedMatrix {
+        let stacks = columns.map(|column| &matrix.stacks[column]);
+
+        let mut writer = BitWriter { words: [0; 3], position: 0 };
+        for stack in &stacks {
+            writer.write(stack.cards.len() as u64, LEN_BITS);
+            writer.write(stack.cheated as u64 | (stack.collapsed as u64) << 1, FLAG_BITS);
+        }
+        for stack in &stacks {
+            for card in &stack.cards {
+                writer.write(*card as u64, CARD_BITS);
+            }
+        }
+        PackedMatrix(writer.words)
+    }
+
+    /// The board with its stacks in sorted order and no move history.
+    pub fn unpack(&self) -> Matrix {
+        let mut matrix: Matrix = Default::default();
+        let mut reader = BitReader { words: &self.0, position: 0 };
+        let mut lens = [0; 6];
+        for (len, stack) in lens.iter_mut().zip(matrix.stacks.iter_mut()) {
+            *len = reader.read(LEN_BITS) as usize;
+            let flags = reader.read(FLAG_BITS);
+            stack.cheated = flags & 1 == 1;
+            stack.collapsed = flags & 2 == 2;
+        }
+        for (len, stack) in lens.iter().zip(matrix.stacks.iter_mut()) {
+            for _ in 0..*len {
+                stack.cards.push(Card::from_value(reader.read(CARD_BITS) as usize).unwrap());
+            }
+        }
+        matrix
+    }
+}
+
+impl From<&Matrix> for PackedMatrix {
+    fn from(matrix: &Matrix) -> Self {
+        PackedMatrix::from_columns(matrix, &matrix.canonical_columns())
+    }
+}
+
+impl Matrix {
+    pub fn pack(&self) -> PackedMatrix {
+        PackedMatrix::from(self)
+    }
+
+    /// Columns in the order their stacks are packed in, equal stacks keep their column order.
+    ///
+    /// Boards that pack the same hold the same stack at the same position of this order,
+    /// so a move on one of them maps to a move on the other through it.
+    pub fn canonical_columns(&self) -> [usize; 6] {
+        let mut columns = [0, 1, 2, 3, 4, 5];
+        columns.sort_by(|a, b| compare_stacks(&self.stacks[*a], &self.stacks[*b]));
+        columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+    use super::*;
+    use crate::testing::{random_walk, MARKED};
+
+    // boards along random games and one with every kind of stack
+    fn boards(rng: &mut SmallRng) -> Vec<Matrix> {
+        let mut boards = vec![MARKED.parse().unwrap()];
+        for deal in 0..20 {
+            boards.extend(random_walk(deal, true, 60, rng));
+        }
+        boards
+    }
+
+    #[test]
+    fn pack_unpack_round_trip() {
+        let mut rng = SmallRng::seed_from_u64(10);
+        for matrix in boards(&mut rng) {
+            let packed = matrix.pack();
+            let unpacked = packed.unpack();
+            assert!(unpacked.pack() == packed);
+            assert!(PackedMatrix::from_words(packed.to_words()) == packed);
+            for (stack, column) in unpacked.stacks.iter().zip(matrix.canonical_columns()) {
+                let original = &matrix.stacks[column];
+                assert_eq!(stack.cards, original.cards);
+                assert_eq!((stack.cheated, stack.collapsed), (original.cheated, original.collapsed));
+            }
+        }
+    }
+
+    #[test]
+    fn column_order_does_not_matter() {
+        let mut rng = SmallRng::seed_from_u64(11);
+        for matrix in boards(&mut rng) {
+            let mut shuffled = matrix.copy();
+            shuffled.stacks.shuffle(&mut rng);
+            assert!(shuffled.pack() == matrix.pack());
+        }
+        assert!(Matrix::from_deal(0).pack() != Matrix::from_deal(1).pack());
+    }
+}
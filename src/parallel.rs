@@ -0,0 +1,190 @@
+//! Depth-first search on several threads.
+//!
+//! The moves of the starting board are handed out to the threads one at a time, each thread running
+//! a [`Search`] from the board the move leads to. All of them share one set of discovered boards,
+//! so no board is explored twice across threads, and they stop as soon as one of them finds a win.
+//! With a single thread this explores the same boards in the same order as [`find_win`](crate::search::find_win).
+
+use std::{collections::{hash_map::RandomState, HashSet}, hash::BuildHasher, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Mutex}, thread};
+
+use crate::{database::Database, heuristic::Heuristic, matrix::{Matrix, Move}, packed::PackedMatrix, search::{Expansion, Limits, PastMatrices, Search, SearchState, Walk}};
+
+const SHARDS: usize = 64;
+// boards a thread expands between checking whether another one has won
+const STEP_BUDGET: usize = 256;
+
+/// Set of discovered boards shared between threads, split into separately locked shards to keep them from waiting on each other.
+pub struct SharedPast {
+    shards: Vec<Mutex<HashSet<PackedMatrix>>>,
+    hasher: RandomState,
+    len: AtomicUsize,
+}
+
+impl SharedPast {
+    pub fn new() -> SharedPast {
+        SharedPast::from_set(HashSet::new())
+    }
+
+    pub fn from_set(past_matrices: HashSet<PackedMatrix>) -> SharedPast {
+        let shared = SharedPast {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashSet::new())).collect(),
+            hasher: RandomState::new(),
+            len: AtomicUsize::new(0),
+        };
+        for matrix in past_matrices {
+            shared.insert(matrix);
+        }
+        shared
+    }
+
+    /// Adds `matrix`, returning whether it wasn't in the set yet.
+    pub fn insert(&self, matrix: PackedMatrix) -> bool {
+        let shard = self.hasher.hash_one(matrix) as usize % SHARDS;
+        let inserted = self.shards[shard].lock().unwrap().insert(matrix);
+        if inserted {
+            self.len.fetch_add(1, Ordering::Relaxed);
+        }
+        inserted
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn into_set(self) -> HashSet<PackedMatrix> {
+        self.shards.into_iter().flat_map(|shard| shard.into_inner().unwrap()).collect()
+    }
+}
+
+impl Default for SharedPast {
+    fn default() -> Self {
+        SharedPast::new()
+    }
+}
+
+impl PastMatrices for &SharedPast {
+    fn insert(&mut self, matrix: PackedMatrix) -> bool {
+        SharedPast::insert(self, matrix)
+    }
+
+    fn len(&self) -> usize {
+        SharedPast::len(self)
+    }
+}
+
+/// Like [`find_win`](crate::search::find_win), searching on `threads` threads.
+///
+/// When several threads win at about the same time the shortest solution is returned.
+pub fn find_win_parallel(
+    matrix: &Matrix,
+    past_matrices: &mut HashSet<PackedMatrix>,
+    allow_cheats: bool,
+    heuristic: &dyn Heuristic,
+    limits: Limits,
+    threads: usize,
+    database: Option<&Database>,
+) -> Option<Vec<Move>> {
+    let shared = SharedPast::from_set(std::mem::take(past_matrices));
+    let winner_option = search_shared(matrix, &shared, allow_cheats, heuristic, limits, threads, database);
+    *past_matrices = shared.into_set();
+    winner_option
+}
+
+fn search_shared(
+    matrix: &Matrix,
+    mut shared: &SharedPast,
+    allow_cheats: bool,
+    heuristic: &dyn Heuristic,
+    limits: Limits,
+    threads: usize,
+    database: Option<&Database>,
+) -> Option<Vec<Move>> {
+    if (allow_cheats && shared.len() > limits.past) || limits.is_past_deadline() {
+        return None;
+    }
+    let mut root = Walk::new(matrix);
+    // the threads only look up the boards their searches reach from these
+    let Expansion { moves: root_moves, known } = root.expand(&mut shared, allow_cheats, heuristic, database, database.map(Database::winnable));
+    if let Some((mov, moves)) = known {
+        return Some([&[mov], &moves[..]].concat());
+    }
+    if root_moves.is_empty() {
+        return root.matrix.is_win().then(Vec::new);
+    }
+
+    let next_root_move = AtomicUsize::new(0);
+    let won = AtomicBool::new(false);
+    // with the index of the root move they started from
+    let winners: Mutex<Vec<(usize, Vec<Move>)>> = Mutex::new(vec![]);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                while !won.load(Ordering::Relaxed) {
+                    let index = next_root_move.fetch_add(1, Ordering::Relaxed);
+                    let Some(root_move) = root_moves.get(index) else {
+                        break;
+                    };
+                    let child = root.matrix.copy_after_move(*root_move);
+                    let mut search = Search::with_past_matrices(&child, shared, allow_cheats, heuristic, limits);
+                    if let Some(database) = database {
+                        search = search.with_database(database);
+                    }
+                    while !won.load(Ordering::Relaxed) {
+                        match search.step(STEP_BUDGET) {
+                            SearchState::Paused => continue,
+                            SearchState::Won(moves) => {
+                                winners.lock().unwrap().push((index, [&[*root_move], &moves[..]].concat()));
+                                won.store(true, Ordering::Relaxed);
+                            },
+                            SearchState::Failed => break,
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    winners
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .min_by_key(|(index, winner)| (winner.len(), *index))
+        .map(|(_, winner)| winner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{heuristic::HighestOrder, search::find_win};
+
+    fn moves(winner: Option<Vec<Move>>) -> Option<Vec<(usize, usize)>> {
+        winner.map(|moves| moves.iter().map(|mov| (mov.from, mov.to)).collect())
+    }
+
+    #[test]
+    fn one_thread_searches_like_find_win() {
+        for deal in 0..8 {
+            let matrix = Matrix::from_deal(deal);
+            let mut past = HashSet::new();
+            let mut parallel_past = HashSet::new();
+            let winner = find_win(&matrix, &mut past, false, &HighestOrder, Limits::default(), None);
+            let parallel_winner = find_win_parallel(&matrix, &mut parallel_past, false, &HighestOrder, Limits::default(), 1, None);
+            assert_eq!(moves(parallel_winner), moves(winner), "deal {}", deal);
+            assert_eq!(parallel_past.len(), past.len(), "deal {}", deal);
+        }
+    }
+
+    #[test]
+    fn several_threads_find_a_win() {
+        // winnable without cheats
+        let matrix = Matrix::from_deal(1);
+        let winner = find_win_parallel(&matrix, &mut HashSet::new(), false, &HighestOrder, Limits::default(), 4, None).unwrap();
+        let boards = matrix.replay(&winner).unwrap();
+        assert!(boards.last().unwrap().is_win());
+    }
+}
@@ -0,0 +1,156 @@
+//! Racing several search configurations against each other on the same board.
+//!
+//! No single [`Strategy`] and heuristic solves every deal well, so [`race`] runs several [`Entry`]s
+//! at once, each on its own thread, and keeps the shortest solution any of them found
+//! once they've all finished or the time budget runs out.
+
+use std::{collections::HashSet, fmt, sync::Arc, thread, time::{Duration, Instant}};
+
+use crate::{heuristic::{HighestOrder, MoveCount, Weighted}, matrix::{Matrix, Move}, packed::PackedMatrix, search::{Limits, Strategy}};
+
+/// One way of searching for a win.
+#[derive(Clone)]
+pub struct Entry {
+    pub strategy: Strategy,
+    pub heuristic: Weighted,
+    pub allow_cheats: bool,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.strategy, self.heuristic)?;
+        if self.allow_cheats {
+            write!(f, " with cheats")?;
+        }
+        Ok(())
+    }
+}
+
+/// Depth-first with both built-in heuristics and a narrow beam, without cheats and, if allowed, with them.
+pub fn default_entries(allow_cheats: bool) -> Vec<Entry> {
+    let highest_order = Weighted::new().with("highest-order", 1.0, Arc::new(HighestOrder));
+    let move_count = Weighted::new().with("move-count", 1.0, Arc::new(MoveCount));
+    let mut cheat_options = vec![false];
+    if allow_cheats {
+        cheat_options.push(true);
+    }
+
+    let mut entries = vec![];
+    for allow_cheats in cheat_options {
+        for (strategy, heuristic) in [
+            (Strategy::DepthFirst, &highest_order),
+            (Strategy::DepthFirst, &move_count),
+            (Strategy::Beam(10), &highest_order),
+        ] {
+            entries.push(Entry {
+                strategy,
+                heuristic: heuristic.clone(),
+                allow_cheats,
+            });
+        }
+    }
+    entries
+}
+
+/// How a single [`Entry`] did in a [`race`].
+pub struct EntryResult {
+    pub entry: Entry,
+    /// Moves winning the board, if any.
+    pub winner: Option<Vec<Move>>,
+    /// Boards discovered.
+    pub nodes: usize,
+    pub time: Duration,
+}
+
+pub struct Race {
+    /// In the order of the entries.
+    pub results: Vec<EntryResult>,
+}
+
+impl Race {
+    /// The result with the shortest solution, the earlier entry on ties.
+    pub fn best(&self) -> Option<&EntryResult> {
+        self.best_index().map(|index| &self.results[index])
+    }
+
+    pub fn into_best(mut self) -> Option<EntryResult> {
+        self.best_index().map(|index| self.results.swap_remove(index))
+    }
+
+    fn best_index(&self) -> Option<usize> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.winner.as_ref().map(|winner| (winner.len(), index)))
+            .min()
+            .map(|(_, index)| index)
+    }
+}
+
+/// Runs every entry on `matrix` concurrently for at most `budget`.
+///
+/// `limits` apply to every entry, with the deadline moved up to the end of the budget.
+pub fn race(matrix: &Matrix, entries: &[Entry], budget: Duration, limits: Limits) -> Race {
+    let deadline = Instant::now() + budget;
+    let limits = Limits {
+        deadline: Some(limits.deadline.map_or(deadline, |limit| limit.min(deadline))),
+        ..limits
+    };
+
+    let results = thread::scope(|scope| {
+        let handles: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                scope.spawn(move || {
+                    let mut past_matrices: HashSet<PackedMatrix> = HashSet::new();
+                    let start_time = Instant::now();
+                    let winner = entry.strategy.find_win(matrix, &mut past_matrices, entry.allow_cheats, &entry.heuristic, limits, None);
+                    EntryResult {
+                        entry: entry.clone(),
+                        winner,
+                        nodes: past_matrices.len(),
+                        time: start_time.elapsed(),
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    Race { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_shortest_valid_win() {
+        let matrix = Matrix::from_deal(4);
+        let race = race(&matrix, &default_entries(true), Duration::from_secs(60), Limits::default());
+        assert_eq!(race.results.len(), 6);
+        let best = race.best().unwrap();
+        let winner = best.winner.as_ref().unwrap();
+        assert!(matrix.replay(winner).unwrap().last().unwrap().is_win());
+        for result in &race.results {
+            assert!(result.winner.as_ref().is_none_or(|other| other.len() >= winner.len()), "{} found a shorter win", result.entry);
+        }
+    }
+
+    #[test]
+    fn stops_the_losers_when_time_runs_out() {
+        // explores every board reachable without cheats and finds no win
+        let matrix = Matrix::from_deal(216);
+        let entries = &default_entries(false)[..1];
+        let start_time = Instant::now();
+        let unlimited = race(&matrix, entries, Duration::from_secs(600), Limits::default());
+        let full_time = start_time.elapsed();
+        assert!(unlimited.best().is_none());
+
+        let start_time = Instant::now();
+        let raced = race(&matrix, entries, full_time / 10, Limits::default());
+        assert!(start_time.elapsed() < full_time / 2);
+        assert!(raced.results[0].nodes < unlimited.results[0].nodes);
+        assert!(raced.into_best().is_none());
+    }
+}
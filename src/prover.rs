@@ -0,0 +1,166 @@
+//! Complete search deciding whether a board can be won at all.
+//!
+//! Unlike [`find_win`](crate::search::find_win) this ignores every [`Limits`](crate::Limits)
+//! and keeps searching until it either finds a win or has seen every reachable board,
+//! so a failure means the board is unsolvable. The search is iterative, so it can go as deep as
+//! needed, and it only remembers the [`PackedMatrix`] of each board it has seen.
+
+use std::collections::HashSet;
+
+use crate::{heuristic::{Heuristic, HighestOrder}, history::Undo, matrix::{Matrix, Move, MoveValidity}, packed::PackedMatrix, rules::Rules};
+
+#[derive(Debug)]
+pub enum Verdict {
+    /// Won with these moves, not necessarily the shortest way.
+    Solvable(Vec<Move>),
+    /// Every reachable board was explored without a win.
+    Unsolvable,
+    /// The node budget ran out first.
+    Unknown,
+}
+
+pub struct Proof {
+    pub verdict: Verdict,
+    /// Distinct boards explored.
+    pub nodes: usize,
+}
+
+struct Frame {
+    // the move that led here, `None` for the start
+    undo: Option<Undo>,
+    // best first
+    moves: Vec<Move>,
+    next: usize,
+}
+
+impl Frame {
+    fn new(matrix: &mut Matrix, undo: Option<Undo>, allow_cheats: bool) -> Frame {
+        let rules = Rules::new(allow_cheats);
+        let mut moves: Vec<(f32, Move)> = vec![];
+        for mov in matrix.distinct_moves() {
+            if rules.validity(matrix, mov) == MoveValidity::Invalid {
+                continue;
+            }
+            let undo = matrix.make(mov).expect("moves from distinct_moves are valid");
+            moves.push((HighestOrder.score(matrix), mov));
+            matrix.unmake(undo);
+        }
+        moves.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Frame {
+            undo,
+            moves: moves.into_iter().map(|(_, mov)| mov).collect(),
+            next: 0,
+        }
+    }
+}
+
+/// Decides whether `matrix` can be won, giving up with [`Verdict::Unknown`] after `max_nodes` boards if set.
+pub fn prove(matrix: &Matrix, allow_cheats: bool, max_nodes: Option<usize>) -> Proof {
+    let mut start_matrix = matrix.copy();
+    start_matrix.past_moves.clear();
+
+    let mut visited: HashSet<PackedMatrix> = HashSet::new();
+    visited.insert(start_matrix.pack());
+    // one board walked up and down the tree, its `past_moves` being the path to it
+    let mut matrix = start_matrix;
+    let mut frames: Vec<Frame> = vec![Frame::new(&mut matrix, None, allow_cheats)];
+
+    while let Some(frame) = frames.last_mut() {
+        if matrix.is_win() {
+            return Proof {
+                verdict: Verdict::Solvable(matrix.past_moves),
+                nodes: visited.len(),
+            };
+        }
+        if frame.next == frame.moves.len() {
+            if let Some(undo) = frame.undo {
+                matrix.unmake(undo);
+            }
+            frames.pop();
+            continue;
+        }
+        let mov = frame.moves[frame.next];
+        frame.next += 1;
+
+        let undo = matrix.make(mov).expect("moves from distinct_moves are valid");
+        if !visited.insert(matrix.pack()) {
+            matrix.unmake(undo);
+            continue;
+        }
+        if max_nodes.is_some_and(|max_nodes| visited.len() > max_nodes) {
+            return Proof {
+                verdict: Verdict::Unknown,
+                nodes: visited.len(),
+            };
+        }
+        frames.push(Frame::new(&mut matrix, Some(undo), allow_cheats));
+    }
+
+    Proof {
+        verdict: Verdict::Unsolvable,
+        nodes: visited.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::STUCK;
+
+    #[test]
+    fn one_run_away_from_a_win() {
+        let matrix: Matrix = "
+            T T T T 9 .
+            K K K K 8 .
+            D D D D 7 .
+            V V V V 6 .
+            0 0 0 0 . .
+            9 9 9 . . .
+            8 8 8 . . .
+            7 7 7 . . .
+            6 6 6 . . .
+            C C C . . .
+        ".parse().unwrap();
+        for allow_cheats in [false, true] {
+            let Verdict::Solvable(moves) = prove(&matrix, allow_cheats, None).verdict else {
+                panic!("not solvable with cheats {}", allow_cheats);
+            };
+            assert!(matrix.replay(&moves).unwrap().last().unwrap().is_win());
+        }
+    }
+
+    #[test]
+    fn solutions_of_deals_win() {
+        for deal in [1, 4] {
+            let matrix = Matrix::from_deal(deal);
+            let Verdict::Solvable(moves) = prove(&matrix, false, None).verdict else {
+                panic!("deal {} not solvable", deal);
+            };
+            let boards = matrix.replay(&moves).unwrap();
+            assert!(boards.last().unwrap().is_win());
+            assert!(moves.iter().zip(&boards).all(|(mov, board)| board.check_validity(*mov) == MoveValidity::ValidNormal));
+        }
+    }
+
+    #[test]
+    fn stuck_board_is_unsolvable() {
+        let matrix: Matrix = STUCK.parse().unwrap();
+        for allow_cheats in [false, true] {
+            let proof = prove(&matrix, allow_cheats, None);
+            assert!(matches!(proof.verdict, Verdict::Unsolvable), "with cheats {}", allow_cheats);
+        }
+    }
+
+    #[test]
+    fn gives_up_at_the_node_limit() {
+        // unsolvable without cheats, after a few ten thousand boards
+        let matrix = Matrix::from_deal(216);
+        let proof = prove(&matrix, false, Some(1_000));
+        assert!(matches!(proof.verdict, Verdict::Unknown));
+        assert!(proof.nodes <= 1_001);
+
+        let proof = prove(&matrix, false, None);
+        assert!(matches!(proof.verdict, Verdict::Unsolvable));
+        assert!(proof.nodes > 1_000);
+    }
+}
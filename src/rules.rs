@@ -0,0 +1,395 @@
+//! The rules of the game, as the solver plays it.
+//!
+//! 1. The board has six stacks holding four sets of the nine values `6 7 8 9 0 V D K T`, from Six up to Tits.
+//!    The last card of a stack is its top, and a [`Move`] takes `count` cards off the top of stack `from`
+//!    and puts them on top of stack `to` in the same order.
+//! 2. Moving onto the stack the cards come from, off a collapsed stack or onto a collapsed or cheated stack is invalid.
+//! 3. A single card can go on an empty stack or on the value right above its own, e.g. a Nine on a Ten.
+//! 4. Several cards can only move together as the whole descending run on top of their stack, see
+//!    [`Stack::highest_orderly_count`](crate::Stack::highest_orderly_count), onto an empty stack
+//!    or onto the value right above the lowest card of the run.
+//! 5. A single card can also be cheated onto a stack it can't go on by rule 3, whatever card is on top of it.
+//!    The stack it's put on becomes cheated. The top card of a cheated stack can't be cheated again,
+//!    it can only leave by rule 3.
+//! 6. Taking cards off a stack makes it no longer cheated.
+//! 7. A stack holding all nine values in order after a move collapses, Tits at the bottom and Six on top.
+//!    It stays on the board but can't be moved from or onto anymore.
+//! 8. The game is won once four stacks have collapsed.
+//!
+//! [`Rules`] decides moves by these rules, optionally without cheats, and tells which rule an invalid move breaks
+//! with a [`MoveError`]. [`Matrix::check_validity`] and [`Matrix::try_apply`] follow them with cheats allowed, as the game does.
+
+use std::{error::Error, fmt};
+
+use crate::matrix::{Matrix, Move, MoveValidity};
+
+/// Cards in a collapsed stack, one of each value.
+pub const COLLAPSE_LEN: usize = 9;
+/// Collapsed stacks needed to win.
+pub const COLLAPSED_TO_WIN: usize = 4;
+
+/// Why a move can't be made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// A stack number past the six stacks.
+    NoSuchStack,
+    /// Moving onto the stack the cards come from.
+    SameStack,
+    SourceEmpty,
+    SourceCollapsed,
+    TargetCollapsed,
+    TargetCheated,
+    /// More cards than the descending run on top of the source, or none at all.
+    CountExceedsRun { count: usize, run: usize },
+    /// Several cards, but not the whole run on top of the source.
+    SplitsRun { count: usize, run: usize },
+    /// The lowest moved card doesn't go on the target's top card, and it can't be cheated there.
+    NotOrderly,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack => write!(f, "there are only 6 stacks"),
+            MoveError::SameStack => write!(f, "cards can't move onto their own stack"),
+            MoveError::SourceEmpty => write!(f, "the source stack is empty"),
+            MoveError::SourceCollapsed => write!(f, "the source stack is collapsed"),
+            MoveError::TargetCollapsed => write!(f, "the target stack is collapsed"),
+            MoveError::TargetCheated => write!(f, "the target stack is cheated"),
+            MoveError::CountExceedsRun { count, run } => {
+                write!(f, "can't move {} cards, the run on top of the source has {}", count, run)
+            },
+            MoveError::SplitsRun { count, run } => {
+                write!(f, "can't move {} of the {} cards of the run on top of the source, runs only move whole", count, run)
+            },
+            MoveError::NotOrderly => write!(f, "the cards don't go on the target stack"),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+/// Which of the rules apply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    /// Whether single cards may be cheated onto stacks by rule 5.
+    pub allow_cheats: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::WITH_CHEATS
+    }
+}
+
+impl Rules {
+    /// The rules of the game.
+    pub const WITH_CHEATS: Rules = Rules { allow_cheats: true };
+    /// The rules of the game without rule 5.
+    pub const WITHOUT_CHEATS: Rules = Rules { allow_cheats: false };
+
+    pub fn new(allow_cheats: bool) -> Rules {
+        Rules { allow_cheats }
+    }
+
+    /// Whether `mov` is a normal move or a cheat on `matrix`, cheats being [`MoveValidity::Invalid`] unless allowed.
+    pub fn validity(&self, matrix: &Matrix, mov: Move) -> MoveValidity {
+        self.check(matrix, mov).unwrap_or(MoveValidity::Invalid)
+    }
+
+    /// Like [`Rules::validity`], saying which rule an invalid move breaks, valid moves are never [`MoveValidity::Invalid`].
+    pub fn check(&self, matrix: &Matrix, mov: Move) -> Result<MoveValidity, MoveError> {
+        let Move { from, to, count } = mov;
+        if from >= matrix.stacks.len() || to >= matrix.stacks.len() {
+            return Err(MoveError::NoSuchStack);
+        }
+        let (source, target) = (&matrix.stacks[from], &matrix.stacks[to]);
+        if to == from {
+            return Err(MoveError::SameStack);
+        }
+        if source.collapsed {
+            return Err(MoveError::SourceCollapsed);
+        }
+        if target.collapsed {
+            return Err(MoveError::TargetCollapsed);
+        }
+        if target.cheated {
+            return Err(MoveError::TargetCheated);
+        }
+        if source.cards.is_empty() {
+            return Err(MoveError::SourceEmpty);
+        }
+        let run = source.highest_orderly_count();
+        if count == 0 || count > run {
+            return Err(MoveError::CountExceedsRun { count, run });
+        }
+        if count > 1 && count < run {
+            return Err(MoveError::SplitsRun { count, run });
+        }
+
+        if target.is_orderly(source.cards[source.cards.len() - count]) {
+            Ok(MoveValidity::ValidNormal)
+        } else if count == 1 && !source.cheated && self.allow_cheats {
+            Ok(MoveValidity::ValidCheat)
+        } else {
+            Err(MoveError::NotOrderly)
+        }
+    }
+
+    /// Makes `mov` on `matrix` if it's valid, collapsing the target stack when it completes.
+    ///
+    /// Returns whether it was a normal move or a cheat. Invalid moves leave the board untouched.
+    /// The move isn't recorded in `past_moves`.
+    pub fn try_apply(&self, matrix: &mut Matrix, mov: Move) -> Result<MoveValidity, MoveError> {
+        let Move { from, to, count } = mov;
+        let validity = self.check(matrix, mov)?;
+
+        let source = &mut matrix.stacks[from];
+        source.cheated = false;
+        let moving_cards = source.cards.split_off(source.cards.len() - count);
+        let target = &mut matrix.stacks[to];
+        target.cards.extend(moving_cards);
+        target.cheated = validity == MoveValidity::ValidCheat;
+        if target.cards.len() == COLLAPSE_LEN && target.highest_orderly_count() == COLLAPSE_LEN {
+            target.collapsed = true;
+        }
+        Ok(validity)
+    }
+
+    /// Whether enough stacks have collapsed to win.
+    pub fn is_win(matrix: &Matrix) -> bool {
+        matrix.stacks.iter().filter(|stack| stack.collapsed).count() == COLLAPSED_TO_WIN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Card;
+    use MoveValidity::{Invalid, ValidCheat, ValidNormal};
+
+    /// A board from its stacks bottom to top, a trailing `-` marks a cheated stack and `C` a collapsed one.
+    fn board(stacks: [&str; 6]) -> Matrix {
+        let mut matrix = Matrix::default();
+        for (stack, text) in matrix.stacks.iter_mut().zip(stacks) {
+            for character in text.chars().filter(|character| !character.is_whitespace()) {
+                match character {
+                    '-' => stack.cheated = true,
+                    'C' => stack.collapsed = true,
+                    _ => stack.cards.push(Card::from_char(character).unwrap()),
+                }
+            }
+        }
+        matrix
+    }
+
+    fn mov(from: usize, to: usize, count: usize) -> Move {
+        Move { from, to, count }
+    }
+
+    fn validity(matrix: &Matrix, mov: Move) -> (MoveValidity, MoveValidity) {
+        (Rules::WITH_CHEATS.validity(matrix, mov), Rules::WITHOUT_CHEATS.validity(matrix, mov))
+    }
+
+    #[test]
+    fn single_card_onto_the_value_above() {
+        let matrix = board(["T 0", "8 9", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(1, 0, 1)), (ValidNormal, ValidNormal));
+    }
+
+    #[test]
+    fn single_card_onto_an_empty_stack() {
+        let matrix = board(["T 0", "8 9", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 2, 1)), (ValidNormal, ValidNormal));
+    }
+
+    #[test]
+    fn single_card_out_of_order_is_a_cheat() {
+        let matrix = board(["T 0", "8 9", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 1, 1)), (ValidCheat, Invalid));
+        // nor on a lower value
+        let matrix = board(["T 6", "8 9", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(1, 0, 1)), (ValidCheat, Invalid));
+    }
+
+    #[test]
+    fn cheated_card_can_only_move_normally() {
+        let matrix = board(["T 6 -", "8 7", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 1, 1)), (ValidNormal, ValidNormal));
+        assert_eq!(validity(&matrix, mov(0, 2, 1)), (ValidNormal, ValidNormal));
+        let matrix = board(["T 6 -", "8 9", "V", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 1, 1)), (Invalid, Invalid));
+        assert_eq!(validity(&matrix, mov(0, 2, 1)), (Invalid, Invalid));
+    }
+
+    #[test]
+    fn nothing_goes_onto_a_cheated_stack() {
+        let matrix = board(["T 7 -", "8 6", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(1, 0, 1)), (Invalid, Invalid));
+        assert_eq!(validity(&matrix, mov(1, 0, 2)), (Invalid, Invalid));
+    }
+
+    #[test]
+    fn nothing_moves_from_or_onto_a_collapsed_stack() {
+        let matrix = board(["T K D V 0 9 8 7 6 C", "7", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 2, 1)), (Invalid, Invalid));
+        assert_eq!(validity(&matrix, mov(0, 2, 9)), (Invalid, Invalid));
+        assert_eq!(validity(&matrix, mov(1, 0, 1)), (Invalid, Invalid));
+    }
+
+    #[test]
+    fn nothing_moves_onto_its_own_stack() {
+        let matrix = board(["T K", "", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 0, 1)), (Invalid, Invalid));
+        assert_eq!(validity(&matrix, mov(0, 0, 2)), (Invalid, Invalid));
+    }
+
+    #[test]
+    fn run_onto_the_value_above_its_lowest_card() {
+        let matrix = board(["6 0 9 8", "7 V", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 1, 3)), (ValidNormal, ValidNormal));
+    }
+
+    #[test]
+    fn run_onto_an_empty_stack() {
+        let matrix = board(["6 0 9 8", "7 V", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 2, 3)), (ValidNormal, ValidNormal));
+    }
+
+    #[test]
+    fn run_out_of_order_is_invalid() {
+        // runs are never cheated
+        let matrix = board(["6 0 9 8", "7 D", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 1, 3)), (Invalid, Invalid));
+    }
+
+    #[test]
+    fn only_whole_runs_move_together() {
+        let matrix = board(["6 0 9 8", "7 9", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 1, 2)), (Invalid, Invalid));
+        assert_eq!(validity(&matrix, mov(0, 2, 2)), (Invalid, Invalid));
+        // a single card off the run still moves
+        assert_eq!(validity(&matrix, mov(0, 2, 1)), (ValidNormal, ValidNormal));
+    }
+
+    #[test]
+    fn moving_from_an_empty_stack_is_invalid() {
+        let matrix = board(["T 0", "", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(1, 0, 1)), (Invalid, Invalid));
+        assert_eq!(Rules::WITH_CHEATS.check(&matrix, mov(1, 2, 1)), Err(MoveError::SourceEmpty));
+    }
+
+    #[test]
+    fn moving_more_than_the_run_is_invalid() {
+        // the Ten below the run can't come along
+        let matrix = board(["9 0 7 6", "8", "", "", "", ""]);
+        assert_eq!(validity(&matrix, mov(0, 1, 3)), (Invalid, Invalid));
+        assert_eq!(validity(&matrix, mov(0, 1, 2)), (ValidNormal, ValidNormal));
+        assert_eq!(validity(&matrix, mov(0, 2, 0)), (Invalid, Invalid));
+    }
+
+    #[test]
+    fn errors_name_the_broken_rule() {
+        let matrix = board(["T K D V 0 9 8 7 6 C", "8 6 -", "0 9 8", "7", "V", ""]);
+        let check = |mov| Rules::WITHOUT_CHEATS.check(&matrix, mov);
+        assert_eq!(check(mov(6, 3, 1)), Err(MoveError::NoSuchStack));
+        assert_eq!(check(mov(3, 3, 1)), Err(MoveError::SameStack));
+        assert_eq!(check(mov(0, 5, 1)), Err(MoveError::SourceCollapsed));
+        assert_eq!(check(mov(3, 0, 1)), Err(MoveError::TargetCollapsed));
+        assert_eq!(check(mov(3, 1, 1)), Err(MoveError::TargetCheated));
+        assert_eq!(check(mov(5, 3, 1)), Err(MoveError::SourceEmpty));
+        assert_eq!(check(mov(2, 5, 4)), Err(MoveError::CountExceedsRun { count: 4, run: 3 }));
+        assert_eq!(check(mov(2, 5, 0)), Err(MoveError::CountExceedsRun { count: 0, run: 3 }));
+        assert_eq!(check(mov(2, 5, 2)), Err(MoveError::SplitsRun { count: 2, run: 3 }));
+        assert_eq!(check(mov(3, 4, 1)), Err(MoveError::NotOrderly));
+        assert_eq!(check(mov(2, 3, 3)), Err(MoveError::NotOrderly));
+        assert_eq!(check(mov(2, 4, 3)), Ok(ValidNormal));
+        // the cheated card can't be cheated away even with cheats
+        assert_eq!(Rules::WITH_CHEATS.check(&matrix, mov(1, 4, 1)), Err(MoveError::NotOrderly));
+        assert_eq!(Rules::WITH_CHEATS.check(&matrix, mov(3, 4, 1)), Ok(ValidCheat));
+    }
+
+    #[test]
+    fn invalid_moves_change_nothing() {
+        let mut matrix = board(["T 6 -", "8 9", "V", "", "", ""]);
+        assert_eq!(Rules::WITH_CHEATS.try_apply(&mut matrix, mov(0, 1, 1)), Err(MoveError::NotOrderly));
+        assert!(matrix.stacks[0].cards == [Card::Tits, Card::Six] && matrix.stacks[0].cheated);
+        assert!(matrix.stacks[1].cards == [Card::Eight, Card::Nine] && !matrix.stacks[1].cheated);
+
+        let mut matrix = board(["T 0", "8 9", "", "", "", ""]);
+        assert_eq!(Rules::WITHOUT_CHEATS.try_apply(&mut matrix, mov(0, 1, 1)), Err(MoveError::NotOrderly));
+        assert!(matrix.stacks[1].cards == [Card::Eight, Card::Nine] && !matrix.stacks[1].cheated);
+    }
+
+    #[test]
+    fn cheating_marks_the_target_stack() {
+        let mut matrix = board(["T 0", "8 9", "", "", "", ""]);
+        assert_eq!(Rules::WITH_CHEATS.try_apply(&mut matrix, mov(0, 1, 1)), Ok(ValidCheat));
+        assert!(matrix.stacks[0].cards == [Card::Tits] && !matrix.stacks[0].cheated);
+        assert!(matrix.stacks[1].cards == [Card::Eight, Card::Nine, Card::Ten] && matrix.stacks[1].cheated);
+    }
+
+    #[test]
+    fn moving_off_a_cheated_stack_clears_it() {
+        let mut matrix = board(["T 6 -", "8 7", "", "", "", ""]);
+        assert_eq!(Rules::WITH_CHEATS.try_apply(&mut matrix, mov(0, 1, 1)), Ok(ValidNormal));
+        assert!(matrix.stacks[0].cards == [Card::Tits] && !matrix.stacks[0].cheated);
+        assert!(matrix.stacks[1].cards == [Card::Eight, Card::Seven, Card::Six] && !matrix.stacks[1].cheated);
+    }
+
+    #[test]
+    fn runs_keep_their_order() {
+        let mut matrix = board(["6 0 9 8", "7 V", "", "", "", ""]);
+        assert_eq!(Rules::WITHOUT_CHEATS.try_apply(&mut matrix, mov(0, 1, 3)), Ok(ValidNormal));
+        assert!(matrix.stacks[0].cards == [Card::Six]);
+        assert!(matrix.stacks[1].cards == [Card::Seven, Card::Viva, Card::Ten, Card::Nine, Card::Eight]);
+    }
+
+    #[test]
+    fn nine_cards_in_order_collapse() {
+        let mut matrix = board(["T K D V 0 9 8 7", "6", "", "", "", ""]);
+        assert_eq!(Rules::WITHOUT_CHEATS.try_apply(&mut matrix, mov(1, 0, 1)), Ok(ValidNormal));
+        assert!(matrix.stacks[0].collapsed);
+
+        let mut matrix = board(["T K D V", "0 9 8 7 6", "", "", "", ""]);
+        assert_eq!(Rules::WITHOUT_CHEATS.try_apply(&mut matrix, mov(1, 0, 5)), Ok(ValidNormal));
+        assert!(matrix.stacks[0].collapsed);
+    }
+
+    #[test]
+    fn nine_cards_out_of_order_dont_collapse() {
+        let mut matrix = board(["T K D V 0 9 8 6", "7", "", "", "", ""]);
+        assert_eq!(Rules::WITH_CHEATS.try_apply(&mut matrix, mov(1, 0, 1)), Ok(ValidCheat));
+        assert!(!matrix.stacks[0].collapsed && matrix.stacks[0].cheated);
+
+        // a run of eight without the Tits doesn't collapse
+        let mut matrix = board(["K D V 0 9 8 7", "6", "", "", "", ""]);
+        assert_eq!(Rules::WITHOUT_CHEATS.try_apply(&mut matrix, mov(1, 0, 1)), Ok(ValidNormal));
+        assert!(!matrix.stacks[0].collapsed);
+    }
+
+    #[test]
+    fn four_collapsed_stacks_win() {
+        let collapsed = "T K D V 0 9 8 7 6 C";
+        assert!(Rules::is_win(&board([collapsed, collapsed, collapsed, collapsed, "", ""])));
+        assert!(!Rules::is_win(&board([collapsed, collapsed, collapsed, "T K D V 0 9 8 7 6", "", ""])));
+        assert!(!Rules::is_win(&board([collapsed, collapsed, collapsed, "", "", ""])));
+    }
+
+    #[test]
+    fn winning_by_collapsing_the_fourth_stack() {
+        let collapsed = "T K D V 0 9 8 7 6 C";
+        let mut matrix = board([collapsed, collapsed, collapsed, "T K D V 0 9 8 7", "6", ""]);
+        assert!(!Rules::is_win(&matrix));
+        assert_eq!(Rules::WITH_CHEATS.try_apply(&mut matrix, mov(4, 3, 1)), Ok(ValidNormal));
+        assert!(Rules::is_win(&matrix));
+    }
+
+    #[test]
+    fn matrix_follows_the_rules_with_cheats() {
+        let matrix = board(["T 0", "8 9", "", "", "", ""]);
+        for mov in [mov(1, 0, 1), mov(0, 2, 1), mov(0, 1, 1), mov(0, 1, 2), mov(0, 0, 1)] {
+            assert_eq!(matrix.check_validity(mov), Rules::WITH_CHEATS.validity(&matrix, mov));
+        }
+    }
+}
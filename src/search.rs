@@ -0,0 +1,596 @@
+use std::{collections::{HashMap, HashSet}, fmt, time::Instant};
+
+use crate::{beam::find_win_beam, cache::WinnableCache, database::Database, dead::is_dead, heuristic::Heuristic, history::Undo, matrix::{Matrix, Move, MoveValidity}, packed::PackedMatrix, parallel::find_win_parallel, rules::Rules};
+
+// strike a balance between fast, non-breaking, not missing a solve too often
+pub const STEP_LIMIT: usize = 2000;
+pub const PAST_LIMIT: usize = 20000;
+pub const ACCEPTABLE_SOLUTION_LEN: usize = 100;
+
+/// Bounds on how long a search is allowed to run, the step and past limits are only enforced when cheats are allowed.
+#[derive(Clone, Copy)]
+pub struct Limits {
+    /// Give up on a path after this many moves.
+    pub step: usize,
+    /// Give up on a deal after discovering this many boards.
+    pub past: usize,
+    /// Solutions longer than this aren't worth executing, a new deal is faster.
+    pub acceptable_solution_len: usize,
+    /// Give up once this time has passed, with or without cheats.
+    pub deadline: Option<Instant>,
+}
+
+impl Limits {
+    pub fn is_past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            step: STEP_LIMIT,
+            past: PAST_LIMIT,
+            acceptable_solution_len: ACCEPTABLE_SOLUTION_LEN,
+            deadline: None,
+        }
+    }
+}
+
+/// Set of the boards a [`Search`] has discovered.
+///
+/// Implemented by plain sets and by [`SharedPast`](crate::parallel::SharedPast) for searching on several threads.
+pub trait PastMatrices {
+    /// Adds `matrix`, returning whether it wasn't in the set yet.
+    fn insert(&mut self, matrix: PackedMatrix) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl PastMatrices for HashSet<PackedMatrix> {
+    fn insert(&mut self, matrix: PackedMatrix) -> bool {
+        HashSet::insert(self, matrix)
+    }
+
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+}
+
+/// How far a [`Search`] has got.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    /// Boards discovered so far.
+    pub nodes: usize,
+    /// Moves on the path currently being explored.
+    pub depth: usize,
+}
+
+pub enum SearchState {
+    /// The node budget of [`Search::step`] ran out, call it again to continue.
+    Paused,
+    /// Found a win, holding the moves from the starting board.
+    Won(Vec<Move>),
+    /// Nothing left to explore, or the past limit or deadline was hit.
+    Failed,
+}
+
+struct Frame {
+    // the move that led here, `None` for the starting board
+    undo: Option<Undo>,
+    // best first
+    moves: Vec<Move>,
+    next: usize,
+}
+
+/// The moves worth exploring from a board, see [`Walk::expand`].
+pub(crate) struct Expansion {
+    /// Moves to boards that weren't discovered before, best first.
+    pub moves: Vec<Move>,
+    /// The one of them leading to the board known to the cache with the fewest moves left, with those moves.
+    pub known: Option<(Move, Vec<Move>)>,
+}
+
+/// A single board walked up and down the tree of a search with [`Matrix::make`] and [`Matrix::unmake`].
+pub(crate) struct Walk {
+    /// Its `past_moves` are the moves that led here.
+    pub matrix: Matrix,
+    // the same board, every move is tried on this one in `expand` so heuristics can see both boards
+    child: Matrix,
+}
+
+impl Walk {
+    pub fn new(matrix: &Matrix) -> Walk {
+        Walk {
+            matrix: matrix.copy(),
+            child: matrix.copy(),
+        }
+    }
+
+    /// Makes `mov`, which has to be valid.
+    pub fn make(&mut self, mov: Move) -> Undo {
+        self.child.make(mov).expect("moves from distinct_moves are valid");
+        self.matrix.make(mov).expect("moves from distinct_moves are valid")
+    }
+
+    pub fn unmake(&mut self, undo: Undo) {
+        self.child.unmake(undo);
+        self.matrix.unmake(undo);
+    }
+
+    /// Makes every move in turn and takes it back, keeping the ones to boards that weren't in `past_matrices` yet
+    /// and aren't dead, by [`is_dead`] or by `database`.
+    ///
+    /// Every board reached is added to `past_matrices`, dead ones too, so they're only ever checked once.
+    /// Won boards are kept even when they were reached before, as every solution ends on the same one.
+    pub fn expand(
+        &mut self,
+        past_matrices: &mut impl PastMatrices,
+        allow_cheats: bool,
+        heuristic: &dyn Heuristic,
+        database: Option<&Database>,
+        cache: Option<&WinnableCache>,
+    ) -> Expansion {
+        let rules = Rules::new(allow_cheats);
+        let mut children: Vec<(f32, Move, Option<Vec<Move>>)> = vec![];
+        for mov in self.matrix.distinct_moves() {
+            if rules.validity(&self.matrix, mov) == MoveValidity::Invalid {
+                continue;
+            }
+            let undo = self.child.make(mov).expect("moves from distinct_moves are valid");
+            let matrix = &self.child;
+            if (past_matrices.insert(matrix.pack()) || matrix.is_win())
+                && !is_dead(matrix, allow_cheats)
+                && !database.is_some_and(|database| database.is_dead(matrix, allow_cheats))
+            {
+                let known = cache.and_then(|cache| cache.get(matrix, allow_cheats));
+                children.push((heuristic.score_move(&self.matrix, mov, matrix), mov, known));
+            }
+            self.child.unmake(undo);
+        }
+
+        children.sort_by(|(a, ..), (b, ..)| a.total_cmp(b));
+        let known = children
+            .iter_mut()
+            .filter_map(|(_, mov, known)| Some((*mov, known.take()?)))
+            .min_by_key(|(_, moves)| moves.len());
+        Expansion {
+            moves: children.into_iter().map(|(_, mov, _)| mov).collect(),
+            known,
+        }
+    }
+}
+
+/// Depth-first search for any win, exploring moves in the order given by `heuristic`.
+///
+/// The path being explored is kept in an explicit stack instead of recursing,
+/// so the search can go arbitrarily deep and be paused in between calls to [`Search::step`].
+/// A single board is walked up and down the tree, the path only holding the moves still to explore at each depth.
+/// Every board discovered is remembered and never explored twice.
+pub struct Search<'a, P: PastMatrices = HashSet<PackedMatrix>> {
+    // at the end of the path
+    walk: Walk,
+    frames: Vec<Frame>,
+    // the board walked to still has to be expanded before moving on to the next move in `frames`, it was reached with this move
+    pending: Option<Option<Undo>>,
+    past_matrices: P,
+    allow_cheats: bool,
+    heuristic: &'a dyn Heuristic,
+    limits: Limits,
+    cache: Option<&'a WinnableCache>,
+    database: Option<&'a Database>,
+    // moves the starting board was reached with
+    start_len: usize,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(matrix: &Matrix, allow_cheats: bool, heuristic: &'a dyn Heuristic, limits: Limits) -> Search<'a> {
+        Search::with_past_matrices(matrix, HashSet::new(), allow_cheats, heuristic, limits)
+    }
+}
+
+impl<'a, P: PastMatrices> Search<'a, P> {
+    /// A search that skips every board in `past_matrices`.
+    pub fn with_past_matrices(matrix: &Matrix, past_matrices: P, allow_cheats: bool, heuristic: &'a dyn Heuristic, limits: Limits) -> Search<'a, P> {
+        Search {
+            walk: Walk::new(matrix),
+            frames: vec![],
+            pending: Some(None),
+            past_matrices,
+            allow_cheats,
+            heuristic,
+            limits,
+            cache: None,
+            database: None,
+            start_len: matrix.past_moves.len(),
+        }
+    }
+
+    /// Also wins on discovering a board known to `cache`, finishing with the cached moves.
+    ///
+    /// Only boards reached by a move are looked up, so searches repeated with the same discovered boards
+    /// don't keep returning the cached moves of the starting board, look it up with [`WinnableCache::get`] instead.
+    pub fn with_cache(mut self, cache: &'a WinnableCache) -> Search<'a, P> {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Like [`Search::with_cache`] with the winnable boards of `database`, also skipping its dead boards.
+    pub fn with_database(mut self, database: &'a Database) -> Search<'a, P> {
+        self.cache = Some(database.winnable());
+        self.database = Some(database);
+        self
+    }
+
+    /// Expands at most `budget` boards.
+    ///
+    /// Stepping again after a win carries on with the rest of the tree.
+    pub fn step(&mut self, budget: usize) -> SearchState {
+        let mut expanded = 0;
+        loop {
+            if let Some(undo) = self.pending.take() {
+                if expanded == budget {
+                    self.pending = Some(undo);
+                    return SearchState::Paused;
+                }
+                expanded += 1;
+                if (self.allow_cheats && self.past_matrices.len() > self.limits.past) || self.limits.is_past_deadline() {
+                    self.leave(undo);
+                    return SearchState::Failed;
+                }
+                // println!("{}", self.walk.matrix.canonical_string());
+
+                let Expansion { moves, known } = self.walk.expand(&mut self.past_matrices, self.allow_cheats, self.heuristic, self.database, self.cache);
+                let matrix = &self.walk.matrix;
+                let past_moves = &matrix.past_moves[self.start_len..];
+                let winner_option = match known {
+                    Some((mov, moves)) => Some([past_moves, &[mov], &moves[..]].concat()),
+                    None if moves.is_empty() && matrix.is_win() => Some(past_moves.to_vec()),
+                    None => None,
+                };
+                if winner_option.is_none() && !moves.is_empty() && !(self.allow_cheats && matrix.past_moves.len() > self.limits.step) {
+                    self.frames.push(Frame { undo, moves, next: 0 });
+                    continue;
+                }
+                self.leave(undo);
+                if let Some(winner) = winner_option {
+                    return SearchState::Won(winner);
+                }
+                continue;
+            }
+
+            let Some(frame) = self.frames.last_mut() else {
+                return SearchState::Failed;
+            };
+            if frame.next == frame.moves.len() {
+                let undo = frame.undo;
+                self.frames.pop();
+                self.leave(undo);
+                continue;
+            }
+            let mov = frame.moves[frame.next];
+            frame.next += 1;
+            self.pending = Some(Some(self.walk.make(mov)));
+        }
+    }
+
+    // takes back the move that led to the current board, back to the board of the frame before
+    fn leave(&mut self, undo: Option<Undo>) {
+        if let Some(undo) = undo {
+            self.walk.unmake(undo);
+        }
+    }
+
+    /// Runs until a win is found or there's nothing left to explore.
+    pub fn run(&mut self) -> Option<Vec<Move>> {
+        self.run_with_progress(usize::MAX, |_| {})
+    }
+
+    /// Like [`Search::run`], calling `report` after every `interval` boards expanded.
+    pub fn run_with_progress(&mut self, interval: usize, mut report: impl FnMut(Progress)) -> Option<Vec<Move>> {
+        loop {
+            match self.step(interval) {
+                SearchState::Paused => report(self.progress()),
+                SearchState::Won(moves) => return Some(moves),
+                SearchState::Failed => return None,
+            }
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            nodes: self.past_matrices.len(),
+            depth: match self.frames.len() {
+                0 => 0,
+                len => self.start_len + len - 1,
+            },
+        }
+    }
+
+    pub fn past_matrices(&self) -> &P {
+        &self.past_matrices
+    }
+
+    pub fn into_past_matrices(self) -> P {
+        self.past_matrices
+    }
+}
+
+/// Runs a [`Search`] until the first win.
+///
+/// Every board discovered is added to `past_matrices` and never explored twice,
+/// so calling this again with the same set finds a different solution, if any.
+/// Returns the moves winning from `matrix`.
+/// With a `database` the search finishes early on its winnable boards and skips its dead ones, see [`Search::with_database`].
+pub fn find_win(
+    matrix: &Matrix,
+    past_matrices: &mut HashSet<PackedMatrix>,
+    allow_cheats: bool,
+    heuristic: &dyn Heuristic,
+    limits: Limits,
+    database: Option<&Database>,
+) -> Option<Vec<Move>> {
+    let mut search = Search::with_past_matrices(matrix, std::mem::take(past_matrices), allow_cheats, heuristic, limits);
+    if let Some(database) = database {
+        search = search.with_database(database);
+    }
+    let winner_option = search.run();
+    *past_matrices = search.into_past_matrices();
+    winner_option
+}
+
+/// How to look for a win.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Strategy {
+    /// [`find_win`], following the best move first.
+    DepthFirst,
+    /// [`find_win_beam`] keeping this many boards per depth.
+    Beam(usize),
+    /// [`find_win_parallel`] on this many threads.
+    ParallelDepthFirst(usize),
+}
+
+impl Strategy {
+    /// Finds a win with this strategy, see [`find_win`] for the arguments.
+    pub fn find_win(
+        &self,
+        matrix: &Matrix,
+        past_matrices: &mut HashSet<PackedMatrix>,
+        allow_cheats: bool,
+        heuristic: &dyn Heuristic,
+        limits: Limits,
+        database: Option<&Database>,
+    ) -> Option<Vec<Move>> {
+        match self {
+            Strategy::DepthFirst => find_win(matrix, past_matrices, allow_cheats, heuristic, limits, database),
+            Strategy::Beam(width) => find_win_beam(matrix, past_matrices, allow_cheats, heuristic, *width, limits, database),
+            Strategy::ParallelDepthFirst(threads) => find_win_parallel(matrix, past_matrices, allow_cheats, heuristic, limits, *threads, database),
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::DepthFirst => write!(f, "depth-first"),
+            Strategy::Beam(width) => write!(f, "beam {}", width),
+            Strategy::ParallelDepthFirst(threads) => write!(f, "depth-first on {} threads", threads),
+        }
+    }
+}
+
+/// Keeps running a [`Search`] past every win until `limits.past` boards have been discovered.
+///
+/// Returns the solutions sorted from shortest to longest.
+/// Without cheats the first solution is returned right away.
+/// Boards known to `cache` finish a search early, and every solution found is added to it.
+pub fn find_multiple_wins(matrix: &Matrix, allow_cheats: bool, heuristic: &dyn Heuristic, limits: Limits, cache: &mut WinnableCache) -> Vec<Vec<Move>> {
+    let mut winners: Vec<Vec<Move>> = vec![];
+    if let Some(moves) = cache.get(matrix, allow_cheats) {
+        winners.push(moves);
+        if !allow_cheats {
+            return winners;
+        }
+    }
+    let mut past_matrices: HashSet<PackedMatrix> = HashSet::new();
+    past_matrices.insert(matrix.pack());
+
+    // every board of a solution is discovered, so starting over would end right away, carry on from the win instead
+    let mut search = Search::with_past_matrices(matrix, past_matrices, allow_cheats, heuristic, limits).with_cache(cache);
+    while let Some(winner) = search.run() {
+        // println!("Found solution: {} moves", winner.len());
+        winners.push(winner);
+        if !allow_cheats {
+            break;
+        }
+    }
+    for winner in &winners {
+        cache.insert(matrix, winner);
+    }
+
+    winners.sort_by_key(|winner| winner.len());
+    winners
+}
+
+/// Shortens solutions by exhaustively searching a few moves around each step of them.
+///
+/// The solutions and the shortest one found are added to `cache`, which lets the exhaustive searches finish early.
+// This expects sorted winners
+pub fn optimize_solutions(start_matrix: &Matrix, winners: &[Vec<Move>], cache: &mut WinnableCache) -> Vec<Move> {
+    for winner in winners {
+        cache.insert(start_matrix, winner);
+    }
+    let mut best = winners[0].clone();
+
+    let starting_cutoff = 2; // I could probably start way earlier, there's a lot of goofing near the end of almost every solve
+
+    for winner in winners {
+        let Ok(winner_past_matrices) = start_matrix.replay(winner) else {
+            continue;
+        };
+        for i in (1..winner_past_matrices.len().saturating_sub(starting_cutoff)).rev() {
+            // TODO: prevent this from firing if it'd take too long (if the bruteforce depth is too big)
+            let past_matrix = &winner_past_matrices[i];
+            let brute_force_depth = best.len() as isize - i as isize;
+            if brute_force_depth > 6 {break;} // brute_force could take too long, stick to 6 or 7 (max 7 or 8)
+            if brute_force_depth < 0 {continue;} // brute_force would immediately end, save some performance
+
+
+            // run bruteforce
+            let mut discovered_matrices: HashMap<PackedMatrix, usize> = HashMap::new();
+            let better_option = brute_force(past_matrix, brute_force_depth as usize, &mut discovered_matrices, cache);
+            if let Some(better) = better_option {
+                if i + better.len() < best.len() {
+                    best = [&winner[..i], &better[..]].concat();
+                    cache.insert(start_matrix, &best);
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Explores every move sequence from `matrix` shorter than `max_len`, returning the shortest win.
+///
+/// Every board discovered is added to `discovered_matrices` with the fewest moves it was reached in,
+/// and skipped when it's reached again in as many moves or more, as everything after it was explored already.
+/// Boards known to `cache` count as wins in their cached number of moves.
+pub fn brute_force(matrix: &Matrix, max_len: usize, discovered_matrices: &mut HashMap<PackedMatrix, usize>, cache: &WinnableCache) -> Option<Vec<Move>> {
+    brute_force_until(matrix, max_len, discovered_matrices, true, cache, None, None)
+}
+
+/// Like [`brute_force`], returning the shortest win found so far once `deadline` has passed
+/// or `max_nodes` boards have been discovered.
+pub(crate) fn brute_force_until(
+    matrix: &Matrix,
+    max_len: usize,
+    discovered_matrices: &mut HashMap<PackedMatrix, usize>,
+    allow_cheats: bool,
+    cache: &WinnableCache,
+    deadline: Option<Instant>,
+    max_nodes: Option<usize>,
+) -> Option<Vec<Move>> {
+    let rules = Rules::new(allow_cheats);
+    let mut best_option: Option<Vec<Move>> = None;
+    let mut frames: Vec<Frame> = vec![];
+    let mut matrix = matrix.copy();
+    matrix.past_moves.clear();
+    // as in `Search`
+    let mut pending: Option<Option<Undo>> = Some(None);
+
+    loop {
+        if let Some(undo) = pending.take() {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                || max_nodes.is_some_and(|max_nodes| discovered_matrices.len() >= max_nodes)
+            {
+                return best_option;
+            }
+            let moves_taken = matrix.past_moves.len();
+            let fewest_moves = discovered_matrices.entry(matrix.pack()).or_insert(usize::MAX);
+            // everything after it was explored with at least as many moves to spare
+            let explored = *fewest_moves <= moves_taken;
+            *fewest_moves = moves_taken.min(*fewest_moves);
+            if !explored && moves_taken < max_len {
+                let beats_best = |len: usize| len < max_len && best_option.as_ref().is_none_or(|best| best.len() > len);
+                if cache.moves_left(&matrix, allow_cheats).is_some_and(|moves_left| beats_best(matrix.past_moves.len() + moves_left)) {
+                    if let Some(moves) = cache.get(&matrix, allow_cheats) {
+                        best_option = Some([&matrix.past_moves[..], &moves[..]].concat());
+                    }
+                }
+                // we don't need to find optimizations for non-cheated runs, only one is good enough anyway
+                let moves: Vec<Move> = matrix
+                    .distinct_moves()
+                    .into_iter()
+                    .filter(|mov| rules.validity(&matrix, *mov) != MoveValidity::Invalid)
+                    .collect();
+                if !moves.is_empty() {
+                    frames.push(Frame { undo, moves, next: 0 });
+                    continue;
+                }
+                if matrix.is_win() && best_option.as_ref().is_none_or(|best| best.len() > matrix.past_moves.len()) {
+                    best_option = Some(matrix.past_moves.clone());
+                }
+            }
+            if let Some(undo) = undo {
+                matrix.unmake(undo);
+            }
+            continue;
+        }
+
+        let Some(frame) = frames.last_mut() else {
+            return best_option;
+        };
+        if frame.next == frame.moves.len() {
+            if let Some(undo) = frame.undo {
+                matrix.unmake(undo);
+            }
+            frames.pop();
+            continue;
+        }
+        let mov = frame.moves[frame.next];
+        frame.next += 1;
+        pending = Some(Some(matrix.make(mov).expect("moves from distinct_moves are valid")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{heuristic::HighestOrder, solution::format_moves};
+
+    fn moves(winner: &[Move]) -> Vec<(usize, usize, usize)> {
+        winner.iter().map(|mov| (mov.from, mov.to, mov.count)).collect()
+    }
+
+    #[test]
+    fn pausing_does_not_change_the_result() {
+        let mut pauses = 0;
+        for deal in 0..6 {
+            let matrix = Matrix::from_deal(deal);
+            for allow_cheats in [false, true] {
+                let mut search = Search::new(&matrix, allow_cheats, &HighestOrder, Limits::default());
+                let winner_option = search.run();
+
+                for budget in [1, 7] {
+                    let mut paused = Search::new(&matrix, allow_cheats, &HighestOrder, Limits::default());
+                    let paused_winner_option = loop {
+                        match paused.step(budget) {
+                            SearchState::Paused => pauses += 1,
+                            SearchState::Won(moves) => break Some(moves),
+                            SearchState::Failed => break None,
+                        }
+                    };
+                    assert_eq!(paused_winner_option.as_deref().map(moves), winner_option.as_deref().map(moves), "deal {}", deal);
+                    assert_eq!(paused.past_matrices().len(), search.past_matrices().len(), "deal {}", deal);
+                }
+            }
+        }
+        assert!(pauses > 0);
+    }
+
+    #[test]
+    fn multiple_wins_are_different_solutions() {
+        let matrix = Matrix::from_deal(4);
+        let mut cache = WinnableCache::new();
+        let winners = find_multiple_wins(&matrix, true, &HighestOrder, Limits::default(), &mut cache);
+        assert!(winners.len() > 1);
+        assert!(winners.windows(2).all(|pair| pair[0].len() <= pair[1].len()));
+        let mut distinct: Vec<String> = winners.iter().map(|winner| format_moves(winner)).collect();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), winners.len());
+        for winner in &winners {
+            assert!(matrix.replay(winner).unwrap().last().unwrap().is_win());
+        }
+        assert_eq!(cache.moves_left(&matrix, true), Some(winners[0].len()));
+
+        // a single one without cheats
+        let winners = find_multiple_wins(&Matrix::from_deal(1), false, &HighestOrder, Limits::default(), &mut WinnableCache::new());
+        assert_eq!(winners.len(), 1);
+    }
+}
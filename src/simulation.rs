@@ -0,0 +1,268 @@
+//! Batch solving of numbered deals with statistics, to measure how changes to the search affect it.
+
+use std::{collections::HashSet, fmt, ops::Range, sync::{atomic::{AtomicU64, Ordering}, Mutex}, thread, time::{Duration, Instant}};
+
+use crate::{database::Database, heuristic::Heuristic, matrix::{Matrix, Move}, packed::PackedMatrix, search::{Limits, Strategy}};
+
+// deals every thread solves against the same database before what they found out is added to it
+const BATCH_DEALS_PER_THREAD: u64 = 8;
+
+/// How solving a single deal ended.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    /// Solved within the acceptable solution length, with this many moves.
+    Solved(usize),
+    /// Solved, but with more moves than the acceptable solution length.
+    TooLong(usize),
+    /// Gave up after discovering more boards than the past limit.
+    PastLimit,
+    /// Ran out of moves to explore, some paths may have been cut short by the step limit.
+    NoSolution,
+}
+
+pub struct DealResult {
+    pub deal: u64,
+    pub outcome: Outcome,
+    /// Boards discovered by the search.
+    pub nodes: usize,
+    pub time: Duration,
+}
+
+// a search with what it found out, for `Database::learn`
+struct Attempt {
+    result: DealResult,
+    matrix: Matrix,
+    winner_option: Option<Vec<Move>>,
+    past_matrices: HashSet<PackedMatrix>,
+}
+
+impl Attempt {
+    fn new(deal: u64, matrix: &Matrix, strategy: Strategy, allow_cheats: bool, heuristic: &dyn Heuristic, limits: Limits, database: Option<&Database>) -> Attempt {
+        let mut past_matrices: HashSet<PackedMatrix> = HashSet::new();
+        let start_time = Instant::now();
+        let winner_option = strategy.find_win(matrix, &mut past_matrices, allow_cheats, heuristic, limits, database);
+        let time = start_time.elapsed();
+
+        let outcome = match &winner_option {
+            Some(winner) if winner.len() <= limits.acceptable_solution_len => Outcome::Solved(winner.len()),
+            Some(winner) => Outcome::TooLong(winner.len()),
+            None if allow_cheats && past_matrices.len() > limits.past => Outcome::PastLimit,
+            None => Outcome::NoSolution,
+        };
+        Attempt {
+            result: DealResult {
+                deal,
+                outcome,
+                nodes: past_matrices.len(),
+                time,
+            },
+            matrix: matrix.copy(),
+            winner_option,
+            past_matrices,
+        }
+    }
+
+    fn teach(self, database: &mut Database, strategy: Strategy, allow_cheats: bool, limits: Limits) -> DealResult {
+        database.learn(&self.matrix, strategy, allow_cheats, limits, self.winner_option.as_deref(), &self.past_matrices);
+        self.result
+    }
+}
+
+/// Solves deal number `deal` with `strategy`.
+pub fn run_deal(deal: u64, strategy: Strategy, allow_cheats: bool, heuristic: &dyn Heuristic, limits: Limits, database: Option<&mut Database>) -> DealResult {
+    run_matrix(deal, &Matrix::from_deal(deal), strategy, allow_cheats, heuristic, limits, database)
+}
+
+/// Solves `matrix` with `strategy`, recording it as deal number `deal`.
+///
+/// The search consults `database` and what it found out is added to it afterwards, see [`Database::learn`].
+pub fn run_matrix(
+    deal: u64,
+    matrix: &Matrix,
+    strategy: Strategy,
+    allow_cheats: bool,
+    heuristic: &dyn Heuristic,
+    limits: Limits,
+    database: Option<&mut Database>,
+) -> DealResult {
+    let attempt = Attempt::new(deal, matrix, strategy, allow_cheats, heuristic, limits, database.as_deref());
+    match database {
+        Some(database) => attempt.teach(database, strategy, allow_cheats, limits),
+        None => attempt.result,
+    }
+}
+
+/// Solves every deal in `deals` on `threads` threads, returning the results ordered by deal number.
+///
+/// The deals are solved in batches, every thread searching with `database` as it was when the batch started,
+/// and what they found out is added to it in between, so deals solved later can make use of it.
+pub fn run_deals(
+    deals: Range<u64>,
+    strategy: Strategy,
+    allow_cheats: bool,
+    heuristic: &dyn Heuristic,
+    limits: Limits,
+    threads: usize,
+    database: Option<&mut Database>,
+) -> Vec<DealResult> {
+    let mut results: Vec<DealResult> = match database {
+        None => run_parallel(deals, threads, |deal| run_deal(deal, strategy, allow_cheats, heuristic, limits, None)),
+        Some(database) => {
+            let batch_len = threads.max(1) as u64 * BATCH_DEALS_PER_THREAD;
+            let mut results = vec![];
+            let mut batch_start = deals.start;
+            while batch_start < deals.end {
+                let batch = batch_start..batch_start.saturating_add(batch_len).min(deals.end);
+                batch_start = batch.end;
+                let snapshot: &Database = database;
+                let mut attempts = run_parallel(batch, threads, |deal| {
+                    Attempt::new(deal, &Matrix::from_deal(deal), strategy, allow_cheats, heuristic, limits, Some(snapshot))
+                });
+                attempts.sort_by_key(|attempt| attempt.result.deal);
+                results.extend(attempts.into_iter().map(|attempt| attempt.teach(database, strategy, allow_cheats, limits)));
+            }
+            results
+        },
+    };
+    results.sort_by_key(|result| result.deal);
+    results
+}
+
+/// Solves every board in `matrices` on `threads` threads, numbering them by their index.
+pub fn run_matrices(matrices: &[Matrix], strategy: Strategy, allow_cheats: bool, heuristic: &dyn Heuristic, limits: Limits, threads: usize) -> Vec<DealResult> {
+    let mut results = run_parallel(0..matrices.len() as u64, threads, |deal| {
+        run_matrix(deal, &matrices[deal as usize], strategy, allow_cheats, heuristic, limits, None)
+    });
+    results.sort_by_key(|result| result.deal);
+    results
+}
+
+// results in the order the deals finished
+fn run_parallel<T: Send>(deals: Range<u64>, threads: usize, run: impl Fn(u64) -> T + Sync) -> Vec<T> {
+    let next_deal = AtomicU64::new(deals.start);
+    let results: Mutex<Vec<T>> = Mutex::new(vec![]);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let deal = next_deal.fetch_add(1, Ordering::Relaxed);
+                    if deal >= deals.end {
+                        break;
+                    }
+                    let result = run(deal);
+                    results.lock().unwrap().push(result);
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+/// Aggregated statistics over a batch of [`DealResult`]s.
+pub struct Summary {
+    pub deals: usize,
+    pub solved: usize,
+    pub too_long: usize,
+    pub past_limit: usize,
+    pub no_solution: usize,
+    /// Lengths of acceptable solutions, sorted.
+    pub solution_lengths: Vec<usize>,
+    pub total_nodes: usize,
+    /// Time per deal, sorted.
+    pub times: Vec<Duration>,
+}
+
+impl Summary {
+    pub fn new(results: &[DealResult]) -> Summary {
+        let mut summary = Summary {
+            deals: results.len(),
+            solved: 0,
+            too_long: 0,
+            past_limit: 0,
+            no_solution: 0,
+            solution_lengths: vec![],
+            total_nodes: 0,
+            times: vec![],
+        };
+        for result in results {
+            match result.outcome {
+                Outcome::Solved(len) => {
+                    summary.solved += 1;
+                    summary.solution_lengths.push(len);
+                },
+                Outcome::TooLong(_) => summary.too_long += 1,
+                Outcome::PastLimit => summary.past_limit += 1,
+                Outcome::NoSolution => summary.no_solution += 1,
+            }
+            summary.total_nodes += result.nodes;
+            summary.times.push(result.time);
+        }
+        summary.solution_lengths.sort();
+        summary.times.sort();
+        summary
+    }
+
+    pub fn solve_rate(&self) -> f32 {
+        self.solved as f32 / self.deals.max(1) as f32
+    }
+
+    pub fn mean_solution_len(&self) -> f32 {
+        self.solution_lengths.iter().sum::<usize>() as f32 / self.solution_lengths.len().max(1) as f32
+    }
+
+    pub fn median_solution_len(&self) -> usize {
+        median(&self.solution_lengths).unwrap_or(0)
+    }
+
+    pub fn max_solution_len(&self) -> usize {
+        self.solution_lengths.last().copied().unwrap_or(0)
+    }
+
+    pub fn mean_nodes(&self) -> f32 {
+        self.total_nodes as f32 / self.deals.max(1) as f32
+    }
+
+    pub fn mean_time(&self) -> Duration {
+        self.times.iter().sum::<Duration>() / self.deals.max(1) as u32
+    }
+
+    pub fn median_time(&self) -> Duration {
+        median(&self.times).unwrap_or_default()
+    }
+}
+
+fn median<T: Copy>(sorted: &[T]) -> Option<T> {
+    sorted.get(sorted.len() / 2).copied()
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Solved: {}/{} ({:.01}%)", self.solved, self.deals, self.solve_rate() * 100.0)?;
+        writeln!(
+            f,
+            "Solution length: mean {:.02}, median {}, max {}",
+            self.mean_solution_len(),
+            self.median_solution_len(),
+            self.max_solution_len(),
+        )?;
+        writeln!(f, "Nodes explored: mean {:.0}, total {}", self.mean_nodes(), self.total_nodes)?;
+        writeln!(
+            f,
+            "Time per deal: mean {:.04}s, median {:.04}s",
+            self.mean_time().as_secs_f32(),
+            self.median_time().as_secs_f32(),
+        )?;
+        writeln!(f, "Failures:")?;
+        for (name, count) in [
+            ("too long", self.too_long),
+            ("past limit", self.past_limit),
+            ("no solution", self.no_solution),
+        ] {
+            let bar_len = (count * 40).div_ceil(self.deals.max(1));
+            writeln!(f, "  {:<12}{:>6} {}", name, count, "#".repeat(bar_len))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+//! Solutions in a form other tools can consume.
+//!
+//! A [`Solution`] serializes to JSON with the start board and every move alongside the board it leads to:
+//!
+//! ```json
+//! {
+//!   "start": [{"cards": ["D", "K", ...], "collapsed": false, "cheated": false}, ...],
+//!   "steps": [{"move": {"from": 3, "to": 1, "count": 2}, "cheat": false, "matrix": [...]}, ...]
+//! }
+//! ```
+//!
+//! For a more compact form, [`format_moves`] writes the moves as `3->1x2 0->4x1 ...`,
+//! which [`parse_moves`] reads back.
+
+use serde::Serialize;
+
+use crate::{matrix::{Matrix, Move, MoveValidity}, rules::MoveError};
+
+/// A single move of a solution.
+#[derive(Serialize)]
+pub struct Step {
+    #[serde(rename = "move")]
+    pub mov: Move,
+    /// The moved card was placed out of order.
+    pub cheat: bool,
+    /// The board after the move.
+    pub matrix: Matrix,
+}
+
+/// A sequence of moves together with the boards it goes through.
+#[derive(Serialize)]
+pub struct Solution {
+    pub start: Matrix,
+    pub steps: Vec<Step>,
+}
+
+impl Solution {
+    /// Replays `moves` from `start`, failing on the first invalid move with its index in `moves`.
+    pub fn new(start: &Matrix, moves: &[Move]) -> Result<Solution, (usize, MoveError)> {
+        let mut steps: Vec<Step> = vec![];
+        let mut last_matrix = start.copy();
+        last_matrix.past_moves.clear();
+        for (index, mov) in moves.iter().enumerate() {
+            let cheat = last_matrix.check_validity(*mov) == MoveValidity::ValidCheat;
+            last_matrix.try_apply(*mov).map_err(|error| (index, error))?;
+            steps.push(Step {
+                mov: *mov,
+                cheat,
+                matrix: last_matrix.copy(),
+            });
+        }
+        Ok(Solution {
+            start: start.copy(),
+            steps,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        self.steps.iter().map(|step| step.mov).collect()
+    }
+
+    pub fn cheat_count(&self) -> usize {
+        self.steps.iter().filter(|step| step.cheat).count()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Writes moves in the compact format, separated by spaces.
+pub fn format_moves(moves: &[Move]) -> String {
+    moves
+        .iter()
+        .map(|mov| mov.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Reads moves in the compact format, separated by any whitespace.
+pub fn parse_moves(s: &str) -> Result<Vec<Move>, String> {
+    s.split_whitespace().map(|mov| mov.parse()).collect()
+}
@@ -0,0 +1,59 @@
+use serde::Serialize;
+
+use crate::card::Card;
+
+/// A single column of cards, the last card in `cards` is the one on top.
+#[derive(Default, Clone, Serialize)]
+pub struct Stack {
+    pub cards: Vec<Card>,
+    /// All nine values were stacked in order, the stack can no longer be touched.
+    pub collapsed: bool,
+    /// The top card was placed out of order, nothing can be placed on it until it's moved away.
+    pub cheated: bool,
+}
+
+impl Stack {
+    /// Number of cards on top of the stack forming a descending run.
+    pub fn highest_orderly_count(&self) -> usize {
+        let len = self.cards.len();
+        if len < 2 {
+            return len;
+        }
+        let mut ret = 1;
+        while self.cards[len-ret] as usize + 1 == self.cards[len-ret-1] as usize {
+            ret += 1;
+            if ret == self.cards.len() {
+                return ret;
+            }
+        }
+        ret
+    }
+
+    /// Whether `card` can be placed on top of this stack without cheating.
+    pub fn is_orderly(&self, card: Card) -> bool {
+        if self.cards.is_empty() {
+            return true;
+        }
+        let last_stack_card_index = self.cards.len()-1;
+        let last_stack_card_num = self.cards[last_stack_card_index] as usize;
+        let card_num = card as usize;
+        card_num + 1 == last_stack_card_num
+    }
+}
+
+impl std::fmt::Display for Stack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut string: String = String::new();
+        string.push('S');
+        for card in &self.cards {
+            string.push(card.to_char());
+        }
+        if self.cheated {
+            string.push('-');
+        }
+        if self.collapsed {
+            string.push('C');
+        }
+        f.write_str(&string)
+    }
+}
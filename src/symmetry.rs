@@ -0,0 +1,112 @@
+//! Boards that only differ in the order of their columns.
+//!
+//! Where a stack is doesn't matter to the rules, so boards with the same stacks in another column order
+//! play the same and pack into the same [`PackedMatrix`]. [`Matrix::canonical_key`] gives that key together
+//! with the order of the columns it was packed in, through which a move on one of those boards maps
+//! to the same move on any other, see [`Move::to_canonical`] and [`Move::from_canonical`].
+//!
+//! The same goes for the moves of a single board: moving onto either of two equal stacks, like two empty ones,
+//! or from either of two equal stacks leads to boards that only differ in their column order.
+//! [`Matrix::distinct_moves`] keeps only the first of such moves, the searches generate their moves with it.
+
+use std::cmp::Ordering;
+
+use crate::{matrix::{Matrix, Move}, packed::{compare_stacks, PackedMatrix}};
+
+impl Matrix {
+    /// The packed board and the columns in the order they were packed in, see [`Matrix::canonical_columns`].
+    pub fn canonical_key(&self) -> (PackedMatrix, [usize; 6]) {
+        let columns = self.canonical_columns();
+        (PackedMatrix::from_columns(self, &columns), columns)
+    }
+
+    /// The valid moves leading to different boards, apart from the column order.
+    ///
+    /// Of several moves only differing in which of some equal stacks they take cards from or put them on,
+    /// only the one with the lowest columns is kept.
+    pub fn distinct_moves(&self) -> Vec<Move> {
+        let equal = |a: usize, b: usize| compare_stacks(&self.stacks[a], &self.stacks[b]) == Ordering::Equal;
+        self.valid_moves()
+            .into_iter()
+            .filter(|mov| {
+                !(0..mov.from).any(|from| equal(from, mov.from))
+                    && !(0..mov.to).any(|to| to != mov.from && equal(to, mov.to))
+            })
+            .collect()
+    }
+}
+
+impl Move {
+    /// This move on a board as a move between positions in its `columns` from [`Matrix::canonical_key`].
+    pub fn to_canonical(self, columns: &[usize; 6]) -> Move {
+        let position = |column: usize| columns.iter().position(|canonical| *canonical == column).unwrap();
+        Move { from: position(self.from), to: position(self.to), count: self.count }
+    }
+
+    /// The move between positions in `columns` from [`Matrix::canonical_key`] on the board they belong to.
+    pub fn from_canonical(self, columns: &[usize; 6]) -> Move {
+        Move { from: columns[self.from], to: columns[self.to], count: self.count }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+    use super::*;
+    use crate::{heuristic::HighestOrder, search::{find_win, Limits}, testing::random_walk};
+
+    // boards along random games and along solutions, which end with empty stacks and so with equal ones
+    fn boards(rng: &mut SmallRng) -> Vec<Matrix> {
+        let mut boards = vec![];
+        for deal in 0..10 {
+            let matrix = Matrix::from_deal(deal);
+            if let Some(winner) = find_win(&matrix, &mut HashSet::new(), false, &HighestOrder, Limits::default(), None) {
+                boards.extend(matrix.replay(&winner).unwrap());
+            }
+        }
+        for deal in 0..30 {
+            boards.extend(random_walk(deal, true, 80, rng));
+        }
+        boards
+    }
+
+    #[test]
+    fn distinct_moves_reach_every_board() {
+        let mut rng = SmallRng::seed_from_u64(21);
+        let mut skipped = 0;
+        for matrix in boards(&mut rng) {
+            let children = |moves: Vec<Move>| -> HashSet<PackedMatrix> {
+                moves.into_iter().map(|mov| matrix.copy_after_move(mov).pack()).collect()
+            };
+            let valid = matrix.valid_moves();
+            let distinct = matrix.distinct_moves();
+            skipped += valid.len() - distinct.len();
+            assert!(children(valid) == children(distinct), "moves lost on\n{}", matrix);
+        }
+        assert!(skipped > 0);
+    }
+
+    #[test]
+    fn canonical_moves_map_between_column_orders() {
+        let mut rng = SmallRng::seed_from_u64(22);
+        for matrix in boards(&mut rng).iter().step_by(5) {
+            let mut permuted = matrix.copy();
+            permuted.stacks.shuffle(&mut rng);
+            let (key, columns) = matrix.canonical_key();
+            let (permuted_key, permuted_columns) = permuted.canonical_key();
+            assert!(key == permuted_key);
+
+            for mov in matrix.valid_moves() {
+                let canonical = mov.to_canonical(&columns);
+                let back = canonical.from_canonical(&columns);
+                assert_eq!((back.from, back.to, back.count), (mov.from, mov.to, mov.count));
+
+                let permuted_mov = canonical.from_canonical(&permuted_columns);
+                assert!(permuted.copy_after_move(permuted_mov) == matrix.copy_after_move(mov));
+            }
+        }
+    }
+}
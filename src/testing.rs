@@ -0,0 +1,54 @@
+//! Boards shared by the tests of several modules.
+
+use rand::{rngs::SmallRng, seq::SliceRandom};
+
+use crate::{matrix::{Matrix, MoveValidity}, rules::Rules};
+
+/// A board with collapsed, empty and cheated columns, written the way [`Matrix`]'s `Display` prints it.
+pub const MARKED: &str = "\
+T . 6 T K T
+K . T K D K
+D . . D V D
+V . . V 0 V
+0 . . 0 9 0
+9 . . 9 8 9
+8 . . 8 7 8
+7 . . 7 6 7
+6 . . . . 6
+C . - . . .";
+
+/// A board that can't be won, not even with cheats.
+///
+/// The cheated Tits can only leave for an empty stack and none can appear, so the cards under them never move.
+pub const STUCK: &str = "
+    6 6 6 6 0 0
+    7 7 7 7 V V
+    8 8 8 8 D D
+    9 9 9 9 K K
+    T T T T 0 0
+    . . . . V V
+    . . . . D D
+    . . . . K K
+    - - - - - .
+";
+
+/// The boards of a game of at most `steps` random moves from deal number `deal`, starting with the deal itself.
+///
+/// Random games rarely collapse a stack. Every board's `past_moves` holds the moves leading to it.
+pub fn random_walk(deal: u64, allow_cheats: bool, steps: usize, rng: &mut SmallRng) -> Vec<Matrix> {
+    let mut boards = vec![Matrix::from_deal(deal)];
+    for _ in 0..steps {
+        let current = boards.last().unwrap();
+        let moves: Vec<_> = current
+            .valid_moves()
+            .into_iter()
+            .filter(|mov| Rules::new(allow_cheats).validity(current, *mov) != MoveValidity::Invalid)
+            .collect();
+        let Some(mov) = moves.choose(rng) else {
+            break;
+        };
+        let next = current.copy_after_move(*mov);
+        boards.push(next);
+    }
+    boards
+}
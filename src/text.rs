@@ -0,0 +1,294 @@
+//! Human-readable text format for boards.
+//!
+//! A board is written as rows of six columns, one column per stack, with the bottom card
+//! of every stack in the first row, the same way the game lays them out on screen:
+//!
+//! ```text
+//! D 6 8 0 V 7
+//! K T 9 V 7 8
+//! K D T 0 6 9
+//! D T 6 9 0 7
+//! V 8 K T 0 9
+//! 8 7 6 V D K
+//! ```
+//!
+//! Cards are `67890VDKT` (`1` is also accepted for ten), `.` marks an empty slot
+//! below which the column has no more cards. If any stack is cheated or collapsed,
+//! a last row of markers follows with `-` for cheated, `C` for collapsed and `.` for neither.
+//! Whitespace between columns is optional and lines starting with `#` are ignored.
+//! Several boards can be written one after another, separated by empty lines, see [`parse_matrices`].
+//!
+//! Printing a [`Matrix`] and parsing it back gives the same stacks in the same column order.
+
+use std::{fmt, str::FromStr};
+
+use crate::{card::Card, matrix::Matrix};
+use strum::IntoEnumIterator;
+
+const EMPTY: char = '.';
+const CHEATED: char = '-';
+const COLLAPSED: char = 'C';
+
+/// Why a board couldn't be parsed, lines and columns are counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMatrixError {
+    UnknownCharacter { line: usize, column: usize, character: char },
+    TooManyColumns { line: usize, count: usize },
+    /// A card sits above an empty slot in the same column.
+    FloatingCard { line: usize, column: usize },
+    /// Card rows follow the marker row.
+    CardsAfterMarkers { line: usize },
+    /// Every card value has to appear exactly four times.
+    WrongCardCount { card: Card, count: usize },
+    /// A stack marked as collapsed doesn't hold all nine values in order.
+    InvalidCollapse { column: usize },
+    /// A stack marked as cheated has no card on top.
+    InvalidCheat { column: usize },
+}
+
+impl fmt::Display for ParseMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMatrixError::UnknownCharacter { line, column, character } => {
+                write!(f, "line {}, column {}: unknown character '{}'", line, column, character)
+            },
+            ParseMatrixError::TooManyColumns { line, count } => {
+                write!(f, "line {}: {} columns, expected at most 6", line, count)
+            },
+            ParseMatrixError::FloatingCard { line, column } => {
+                write!(f, "line {}, column {}: card above an empty slot", line, column)
+            },
+            ParseMatrixError::CardsAfterMarkers { line } => {
+                write!(f, "line {}: cards after the marker row", line)
+            },
+            ParseMatrixError::WrongCardCount { card, count } => {
+                write!(f, "card '{}' appears {} times, expected 4", card.to_char(), count)
+            },
+            ParseMatrixError::InvalidCollapse { column } => {
+                write!(f, "column {}: marked collapsed but doesn't hold all nine cards in order", column)
+            },
+            ParseMatrixError::InvalidCheat { column } => {
+                write!(f, "column {}: marked cheated but has no cards", column)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ParseMatrixError {}
+
+/// Parses every board in `s`, boards are separated by one or more empty lines.
+///
+/// Line numbers in errors count from the start of `s`.
+pub fn parse_matrices(s: &str) -> Vec<Result<Matrix, ParseMatrixError>> {
+    let mut ret = vec![];
+    let mut board_lines: Vec<(usize, &str)> = vec![];
+
+    for (line_index, line) in s.lines().chain([""]).enumerate() {
+        if !line.trim().is_empty() {
+            board_lines.push((line_index + 1, line));
+            continue;
+        }
+        // chunks of comments only aren't boards
+        if board_lines.iter().any(|(_, line)| !line.trim().starts_with('#')) {
+            ret.push(parse_lines(board_lines.iter().copied()));
+        }
+        board_lines.clear();
+    }
+
+    ret
+}
+
+impl FromStr for Matrix {
+    type Err = ParseMatrixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_lines(s.lines().enumerate().map(|(line_index, line)| (line_index + 1, line)))
+    }
+}
+
+fn parse_lines<'a>(lines: impl Iterator<Item = (usize, &'a str)>) -> Result<Matrix, ParseMatrixError> {
+    let mut matrix: Matrix = Default::default();
+    let mut column_ended = [false; 6];
+    let mut markers_line: Option<usize> = None;
+
+    for (line_number, line) in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let symbols: Vec<char> = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+        if symbols.len() > 6 {
+            return Err(ParseMatrixError::TooManyColumns { line: line_number, count: symbols.len() });
+        }
+        if markers_line.is_some() {
+            return Err(ParseMatrixError::CardsAfterMarkers { line: line_number });
+        }
+
+        // a row without cards but with at least one flag is the marker row
+        if symbols.iter().all(|c| [EMPTY, CHEATED, COLLAPSED].contains(c)) && symbols.iter().any(|c| *c != EMPTY) {
+            for (column, symbol) in symbols.iter().enumerate() {
+                matrix.stacks[column].cheated = *symbol == CHEATED;
+                matrix.stacks[column].collapsed = *symbol == COLLAPSED;
+            }
+            markers_line = Some(line_number);
+            continue;
+        }
+
+        for (column, ended) in column_ended.iter_mut().enumerate() {
+            let symbol = symbols.get(column).copied().unwrap_or(EMPTY);
+            if symbol == EMPTY {
+                *ended = true;
+                continue;
+            }
+            let Some(card) = Card::from_char(symbol) else {
+                return Err(ParseMatrixError::UnknownCharacter { line: line_number, column: column + 1, character: symbol });
+            };
+            if *ended {
+                return Err(ParseMatrixError::FloatingCard { line: line_number, column: column + 1 });
+            }
+            matrix.stacks[column].cards.push(card);
+        }
+    }
+
+    for card in Card::iter() {
+        let count = matrix.stacks
+            .iter()
+            .map(|stack| stack.cards.iter().filter(|c| **c == card).count())
+            .sum();
+        if count != 4 {
+            return Err(ParseMatrixError::WrongCardCount { card, count });
+        }
+    }
+    for (column, stack) in matrix.stacks.iter().enumerate() {
+        if stack.collapsed && (stack.cards.len() != 9 || stack.highest_orderly_count() != 9) {
+            return Err(ParseMatrixError::InvalidCollapse { column: column + 1 });
+        }
+        if stack.cheated && stack.cards.is_empty() {
+            return Err(ParseMatrixError::InvalidCheat { column: column + 1 });
+        }
+    }
+
+    Ok(matrix)
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(|stack| stack.cards.len()).max().unwrap_or(0);
+        let mut rows: Vec<String> = vec![];
+
+        for row in 0..height {
+            let symbols: Vec<String> = self.stacks
+                .iter()
+                .map(|stack| stack.cards.get(row).map_or(EMPTY, |card| card.to_char()).to_string())
+                .collect();
+            rows.push(symbols.join(" "));
+        }
+
+        if self.stacks.iter().any(|stack| stack.cheated || stack.collapsed) {
+            let symbols: Vec<String> = self.stacks
+                .iter()
+                .map(|stack| {
+                    if stack.collapsed {
+                        COLLAPSED
+                    } else if stack.cheated {
+                        CHEATED
+                    } else {
+                        EMPTY
+                    }
+                }.to_string())
+                .collect();
+            rows.push(symbols.join(" "));
+        }
+
+        f.write_str(&rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::testing::{random_walk, MARKED};
+
+    const DEAL: &str = "
+        8 K 8 T 7 6
+        9 8 0 6 T T
+        D 0 D 7 V 9
+        0 V 0 9 V 9
+        7 7 8 K T V
+        D 6 K 6 D K
+    ";
+
+    fn assert_same_stacks(a: &Matrix, b: &Matrix) {
+        for (stack, other) in a.stacks.iter().zip(&b.stacks) {
+            assert_eq!(stack.cards, other.cards);
+            assert_eq!((stack.cheated, stack.collapsed), (other.cheated, other.collapsed));
+        }
+    }
+
+    fn parse_error(text: &str) -> ParseMatrixError {
+        text.parse::<Matrix>().err().unwrap()
+    }
+
+    #[test]
+    fn round_trip_with_markers_and_empty_columns() {
+        let matrix: Matrix = MARKED.parse().unwrap();
+        assert!(matrix.stacks[0].collapsed && matrix.stacks[2].cheated && matrix.stacks[1].cards.is_empty());
+        assert_eq!(matrix.to_string(), MARKED);
+    }
+
+    #[test]
+    fn round_trip_along_random_games() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        for deal in 0..20 {
+            for matrix in random_walk(deal, true, 60, &mut rng) {
+                let parsed: Matrix = matrix.to_string().parse().unwrap();
+                assert_same_stacks(&parsed, &matrix);
+            }
+        }
+    }
+
+    #[test]
+    fn several_boards_and_comments() {
+        let text = format!("# deal 0\n{}\n\n# nothing\n\n{}", DEAL, Matrix::from_deal(1));
+        let matrices = parse_matrices(&text);
+        assert_eq!(matrices.len(), 2);
+        assert_same_stacks(matrices[0].as_ref().unwrap(), &Matrix::from_deal(0));
+        assert_same_stacks(matrices[1].as_ref().unwrap(), &Matrix::from_deal(1));
+    }
+
+    #[test]
+    fn wrong_card_count() {
+        let error = parse_error(&DEAL.replace("D 6 K 6 D K", "D 6 K 6 D ."));
+        assert_eq!(error, ParseMatrixError::WrongCardCount { card: Card::King, count: 3 });
+        assert_eq!(error.to_string(), "card 'K' appears 3 times, expected 4");
+    }
+
+    #[test]
+    fn unknown_character() {
+        let error = parse_error("8 K 8 X 7 6\n9 8 0 6 T T");
+        assert_eq!(error, ParseMatrixError::UnknownCharacter { line: 1, column: 4, character: 'X' });
+        assert_eq!(error.to_string(), "line 1, column 4: unknown character 'X'");
+    }
+
+    #[test]
+    fn too_many_columns() {
+        assert_eq!(parse_error("8 K 8 T 7 6\n9 8 0 6 T T 7"), ParseMatrixError::TooManyColumns { line: 2, count: 7 });
+    }
+
+    #[test]
+    fn card_above_an_empty_slot() {
+        let error = parse_error("8 . 8 T 7 6\n9 8 0 6 T T");
+        assert_eq!(error, ParseMatrixError::FloatingCard { line: 2, column: 2 });
+        assert_eq!(error.to_string(), "line 2, column 2: card above an empty slot");
+    }
+
+    #[test]
+    fn invalid_markers() {
+        // the board starts with an empty line
+        assert_eq!(parse_error(&format!("{}\n- . . . . .\n8 8 8 8 8 8", DEAL)), ParseMatrixError::CardsAfterMarkers { line: 10 });
+        assert_eq!(parse_error(&format!("{}\nC . . . . .", DEAL)), ParseMatrixError::InvalidCollapse { column: 1 });
+        assert_eq!(parse_error(&MARKED.replace("C . - . . .", "C - - . . .")), ParseMatrixError::InvalidCheat { column: 2 });
+    }
+}
@@ -0,0 +1,102 @@
+//! Random search over the weights of the board feature heuristics.
+//!
+//! Every candidate is a [`Weighted`] sum of the [`FEATURES`], scored by solving a fixed corpus of boards
+//! with a [`Strategy`] and measuring the solution lengths and nodes explored, see [`cost`].
+//! The search starts from the weights equivalent to [`HighestOrder`](crate::heuristic::HighestOrder)
+//! and keeps trying random changes to the best weights found so far, making them smaller as it goes.
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{heuristic::{by_name, Weighted}, matrix::Matrix, search::{Limits, Strategy}, simulation::{run_matrices, Summary}};
+
+/// Names of the heuristics whose weights are tuned, see [`by_name`].
+pub const FEATURES: [&str; 5] = ["collapsed-stacks", "orderly-runs", "empty-stacks", "cheated-stacks", "buried-high-cards"];
+
+/// Weights that order moves the same as [`HighestOrder`](crate::heuristic::HighestOrder).
+pub const HIGHEST_ORDER_WEIGHTS: [f32; 5] = [-10.0, -1.0, 0.0, 0.0, 0.0];
+
+// a thousand nodes explored are worth one move
+const NODES_PER_MOVE: f32 = 1000.0;
+// largest change to a weight in the first iteration, relative to the weight but at least this much
+const INITIAL_STEP: f32 = 0.5;
+
+/// Weights of the [`FEATURES`] and how well they did on the corpus.
+pub struct Trial {
+    pub weights: [f32; 5],
+    pub summary: Summary,
+    pub cost: f32,
+}
+
+impl Trial {
+    pub fn heuristic(&self) -> Weighted {
+        heuristic_from_weights(&self.weights)
+    }
+}
+
+/// Sum of the [`FEATURES`] with `weights`, leaving out the ones weighted 0.
+pub fn heuristic_from_weights(weights: &[f32; 5]) -> Weighted {
+    FEATURES
+        .iter()
+        .zip(weights)
+        .filter(|(_, weight)| **weight != 0.0)
+        .fold(Weighted::new(), |weighted, (name, weight)| weighted.with(name, *weight, by_name(name).unwrap()))
+}
+
+/// Lower is better, the mean solution length plus a move per [`NODES_PER_MOVE`] nodes explored.
+///
+/// Boards that weren't solved within the acceptable solution length count as twice that length.
+pub fn cost(summary: &Summary, limits: Limits) -> f32 {
+    let unsolved = summary.deals - summary.solved;
+    let total_len = summary.solution_lengths.iter().sum::<usize>() + unsolved * limits.acceptable_solution_len * 2;
+    total_len as f32 / summary.deals.max(1) as f32 + summary.mean_nodes() / NODES_PER_MOVE
+}
+
+/// The boards and solver settings weights are evaluated with.
+pub struct Tuner<'a> {
+    pub corpus: &'a [Matrix],
+    pub strategy: Strategy,
+    pub allow_cheats: bool,
+    pub limits: Limits,
+    pub threads: usize,
+}
+
+impl Tuner<'_> {
+    /// Solves every board in the corpus with the heuristic built from `weights`.
+    pub fn evaluate(&self, weights: [f32; 5]) -> Trial {
+        let results = run_matrices(self.corpus, self.strategy, self.allow_cheats, &heuristic_from_weights(&weights), self.limits, self.threads);
+        let summary = Summary::new(&results);
+        Trial {
+            weights,
+            cost: cost(&summary, self.limits),
+            summary,
+        }
+    }
+
+    /// Tries `iterations` weight changes after evaluating the starting weights, returning the best trial.
+    ///
+    /// `report` is called after every iteration with its number, the trial and the best one so far.
+    pub fn tune(&self, iterations: usize, seed: u64, mut report: impl FnMut(usize, &Trial, &Trial)) -> Trial {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut best = self.evaluate(HIGHEST_ORDER_WEIGHTS);
+        report(0, &best, &best);
+
+        for iteration in 1..=iterations {
+            let step = INITIAL_STEP * (1.0 - (iteration - 1) as f32 / iterations as f32);
+            let mut weights = best.weights;
+            for weight in &mut weights {
+                *weight += rng.gen_range(-step..=step) * weight.abs().max(1.0);
+                // keep the printed specs short
+                *weight = (*weight * 100.0).round() / 100.0;
+            }
+            let trial = self.evaluate(weights);
+            if trial.cost < best.cost {
+                best = trial;
+                report(iteration, &best, &best);
+            } else {
+                report(iteration, &trial, &best);
+            }
+        }
+
+        best
+    }
+}